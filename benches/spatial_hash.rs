@@ -0,0 +1,80 @@
+//! Compares the spatial hash against the nested loop `EcsWorld::update` used to run
+//! over every pair of colliders. Run with `cargo bench --bench spatial_hash`.
+
+use engine_2d::spatial_hash::SpatialHash;
+use glam::Vec2;
+use hecs::Entity;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ENTITY_COUNT: usize = 10_000;
+const WORLD_SIZE: f32 = 4000.0;
+const ITERATIONS: u32 = 10;
+
+/// Bullet-sized boxes scattered pseudo-randomly over the world.
+fn scattered_boxes() -> Vec<(Entity, Vec2, Vec2)> {
+    let mut world = hecs::World::new();
+    let mut seed = 42u32;
+    let mut next = || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (seed >> 8) as f32 / (1u32 << 24) as f32
+    };
+    (0..ENTITY_COUNT)
+        .map(|_| {
+            let min = Vec2::new(next(), next()) * WORLD_SIZE;
+            let size = Vec2::new(next(), next()) * 24.0 + Vec2::splat(4.0);
+            (world.spawn(()), min, min + size)
+        })
+        .collect()
+}
+
+fn nested_loop(boxes: &[(Entity, Vec2, Vec2)]) -> Vec<(Entity, Entity)> {
+    let mut collisions = Vec::new();
+    for i in 0..boxes.len() {
+        for j in (i + 1)..boxes.len() {
+            let (entity_a, min_a, max_a) = boxes[i];
+            let (entity_b, min_b, max_b) = boxes[j];
+            if max_a.x > min_b.x && min_a.x < max_b.x && max_a.y > min_b.y && min_a.y < max_b.y {
+                collisions.push((entity_a, entity_b));
+            }
+        }
+    }
+    collisions
+}
+
+fn spatial_hash(hash: &mut SpatialHash, boxes: &[(Entity, Vec2, Vec2)]) -> Vec<(Entity, Entity)> {
+    for (entity, min, max) in boxes {
+        hash.insert(*entity, *min, *max);
+    }
+    hash.overlapping_pairs()
+}
+
+fn time(name: &str, mut run: impl FnMut() -> usize) -> Duration {
+    let mut pairs = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        pairs = black_box(run());
+    }
+    let average = start.elapsed() / ITERATIONS;
+    println!("{:<24} {:>10.3?} per frame, {} overlapping pairs", name, average, pairs);
+    average
+}
+
+fn main() {
+    let boxes = scattered_boxes();
+    println!("{} colliders in a {}x{} pixel world", ENTITY_COUNT, WORLD_SIZE, WORLD_SIZE);
+
+    let nested = time("nested loop", || nested_loop(&boxes).len());
+    let rebuilt = time("spatial hash (rebuilt)", || {
+        let mut hash = SpatialHash::default();
+        spatial_hash(&mut hash, &boxes).len()
+    });
+    let mut hash = SpatialHash::default();
+    let incremental = time("spatial hash (updated)", || spatial_hash(&mut hash, &boxes).len());
+
+    println!(
+        "speedup: {:.1}x rebuilt, {:.1}x updated",
+        nested.as_secs_f64() / rebuilt.as_secs_f64(),
+        nested.as_secs_f64() / incremental.as_secs_f64()
+    );
+}
@@ -3,37 +3,54 @@ struct VertexInput {
     @location(1) tex_coords: vec2<f32>,
 };
 
+struct InstanceInput {
+    @location(2) model_matrix_0: vec4<f32>,
+    @location(3) model_matrix_1: vec4<f32>,
+    @location(4) model_matrix_2: vec4<f32>,
+    @location(5) model_matrix_3: vec4<f32>,
+    @location(6) uv_rect: vec4<f32>,
+    @location(7) tint: vec4<f32>,
+};
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) tint: vec4<f32>,
 };
 
-struct TransformUniforms {
-    model_matrix: mat4x4<f32>,
+struct CameraUniforms {
     view_proj_matrix: mat4x4<f32>,
 };
 
 @group(0) @binding(0)
-var<uniform> transform: TransformUniforms;
+var<uniform> camera: CameraUniforms;
 
-@group(0) @binding(1)
+@group(1) @binding(0)
 var t_diffuse: texture_2d<f32>;
 
-@group(0) @binding(2)
+@group(1) @binding(1)
 var s_diffuse: sampler;
 
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(
+    model: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let model_matrix = mat4x4<f32>(
+        instance.model_matrix_0,
+        instance.model_matrix_1,
+        instance.model_matrix_2,
+        instance.model_matrix_3,
+    );
+
     var out: VertexOutput;
-    out.tex_coords = model.tex_coords;
-    
-    var transformed_pos = transform.model_matrix * vec4<f32>(model.position, 0.0, 1.0);
-    out.clip_position = transform.view_proj_matrix * transformed_pos;
-    
+    out.tex_coords = instance.uv_rect.xy + model.tex_coords * instance.uv_rect.zw;
+    out.tint = instance.tint;
+    out.clip_position = camera.view_proj_matrix * model_matrix * vec4<f32>(model.position, 0.0, 1.0);
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex_coords);
+    return textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.tint;
 }
\ No newline at end of file
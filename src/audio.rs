@@ -9,11 +9,19 @@ pub struct AudioSystem {
 
 impl AudioSystem {
     pub fn new() -> Self {
-        let (stream, stream_handle) = OutputStream::try_default().unwrap();
-        let sink = Sink::try_new(&stream_handle).ok();
+        let (stream, sink) = match OutputStream::try_default() {
+            Ok((stream, stream_handle)) => {
+                let sink = Sink::try_new(&stream_handle).ok();
+                (Some(stream), sink)
+            }
+            Err(e) => {
+                log::warn!("No audio output device available, sound is disabled: {}", e);
+                (None, None)
+            }
+        };
         
         Self {
-            _stream: Some(stream),
+            _stream: stream,
             sink,
             sound_effects: HashMap::new(),
         }
@@ -25,12 +33,18 @@ impl AudioSystem {
     }
     
     pub fn play_sound(&self, name: &str) {
-        if let Some(data) = self.sound_effects.get(name) {
-            if let Some(sink) = &self.sink {
-                let cursor = std::io::Cursor::new(data.clone());
-                let source = rodio::Decoder::new(cursor).unwrap();
-                sink.append(source);
-            }
+        if let Some(data) = self.sound_effects.get(name)
+            && let Some(sink) = &self.sink
+        {
+            let cursor = std::io::Cursor::new(data.clone());
+            let source = rodio::Decoder::new(cursor).unwrap();
+            sink.append(source);
         }
     }
+}
+
+impl Default for AudioSystem {
+    fn default() -> Self {
+        Self::new()
+    }
 }
\ No newline at end of file
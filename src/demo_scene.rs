@@ -1,7 +1,61 @@
-use crate::ecs::EcsWorld;
-use glam::Vec2;
+use crate::ecs::{Collider, EcsWorld, RigidBody, Sprite, Transform};
+use crate::prefab::{Overrides, Prefab, Prefabs};
+use crate::shapes::ColliderShape;
+use glam::{Vec2, Vec4};
+
+/// Registers the prefabs the demo scene is built from.
+pub fn register_demo_prefabs(world: &mut EcsWorld) {
+    world.register_prefab(
+        "enemy",
+        Prefab::new()
+            .with("Transform", &Transform { position: Vec2::ZERO, rotation: 0.0, scale: Vec2::ONE })
+            .with(
+                "Sprite",
+                &Sprite {
+                    texture_name: "enemy".to_string(),
+                    width: 80.0,
+                    height: 80.0,
+                    uv_rect: Vec4::new(0.0, 0.0, 1.0, 1.0),
+                    tint: Vec4::ONE,
+                },
+            )
+            .with("RigidBody", &RigidBody::new(Vec2::new(0.0, -30.0), 1.0))
+            .with(
+                "Collider",
+                &Collider {
+                    shape: ColliderShape::cuboid(80.0, 80.0),
+                    is_trigger: false,
+                    collision_filter: Default::default(),
+                    material: Default::default(),
+                },
+            ),
+    );
+}
 
 pub fn load_demo_scene(world: &mut EcsWorld) {
+    if let Err(error) = spawn_demo_entities(world) {
+        log::warn!("Failed to load the demo scene: {}", error);
+    }
+}
+
+fn spawn_demo_entities(world: &mut EcsWorld) -> Result<(), String> {
+    // A game that registered its own "enemy" keeps it; the demo spawns that instead.
+    let enemy_registered = world.resource::<Prefabs>().is_some_and(|prefabs| prefabs.get("enemy").is_some());
+    if !enemy_registered {
+        register_demo_prefabs(world);
+    }
+
+    let ground = world.create_sprite_entity(
+        "player",
+        Vec2::new(100.0, 20.0),
+        1400.0,
+        40.0
+    );
+    world.add_collider(ground, 1400.0, 40.0, false)?;
+    if let Ok(mut sprite) = world.world.get::<&mut Sprite>(ground) {
+        sprite.tint = Vec4::new(0.3, 0.3, 0.3, 1.0);
+    }
+    
     let player = world.create_sprite_entity(
         "player",
         Vec2::new(400.0, 300.0),
@@ -9,18 +63,13 @@ pub fn load_demo_scene(world: &mut EcsWorld) {
         100.0
     );
     
-    world.add_rigid_body(player, Vec2::new(1.0, 0.0), 1.0);
+    world.add_rigid_body(player, Vec2::new(60.0, 0.0), 1.0)?;
     
-    world.add_collider(player, 100.0, 100.0, false);
+    world.add_collider(player, 100.0, 100.0, false)?;
     
     for i in 0..5 {
-        let enemy = world.create_sprite_entity(
-            "enemy",
-            Vec2::new(-300.0 + (i as f32 * 150.0), 200.0),
-            80.0,
-            80.0
-        );
-        world.add_rigid_body(enemy, Vec2::new(0.0, -0.5), 1.0);
-        world.add_collider(enemy, 80.0, 80.0, false);
+        let position = Vec2::new(-300.0 + (i as f32 * 150.0), 200.0);
+        world.spawn_prefab("enemy", Overrides::new().with("Transform", &Transform { position, rotation: 0.0, scale: Vec2::ONE }))?;
     }
+    Ok(())
 }
\ No newline at end of file
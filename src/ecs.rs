@@ -1,41 +1,784 @@
-use hecs::{Entity, World};
-use glam::Vec2;
+use crate::layers::CollisionFilter;
+use crate::prefab::{Overrides, Prefab, Prefabs};
+use crate::scene::{ComponentRegistry, Scene};
+use crate::shapes::ColliderShape;
+use crate::spatial_hash::SpatialHash;
+use hecs::{Bundle, Component, DynamicBundle, Entity, World};
+use serde::de::DeserializeOwned;
+use glam::{Affine2, Mat2, Mat4, Vec2, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+use std::any::{type_name, Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::path::Path;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Transform {
     pub position: Vec2,
     pub rotation: f32,
     pub scale: Vec2,
 }
 
-#[derive(Clone)]
+impl Transform {
+    /// Interpolates towards `other`, taking the shortest way round for rotation.
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        let rotation_delta = (other.rotation - self.rotation + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+
+        Transform {
+            position: self.position.lerp(other.position, t),
+            rotation: self.rotation + rotation_delta * t,
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+}
+
+/// The `Transform` as it was before the latest fixed simulation step, used to
+/// interpolate rendering between steps.
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct PreviousTransform(pub Transform);
+
+/// The entity this one is attached to. Its `Transform` is then relative to the
+/// parent's `GlobalTransform`. Set with `EcsWorld::set_parent` so the parent's
+/// `Children` stay in sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Parent(pub Entity);
+
+/// Entities attached to this one, in the order they were attached.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Children(pub Vec<Entity>);
+
+/// World-space placement of an entity, computed by `propagate_transforms` from its
+/// `Transform` and those of its ancestors. Kept as an affine matrix so non-uniform
+/// scale combined with a rotated child (a shear) is represented exactly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlobalTransform(pub Affine2);
+
+impl GlobalTransform {
+    pub const IDENTITY: Self = Self(Affine2::IDENTITY);
+
+    pub fn from_transform(transform: &Transform) -> Self {
+        Self(Affine2::from_scale_angle_translation(transform.scale, transform.rotation, transform.position))
+    }
+
+    /// Placement of a child whose local transform is `local`.
+    pub fn mul_transform(&self, local: &Transform) -> Self {
+        Self(self.0 * Self::from_transform(local).0)
+    }
+
+    pub fn translation(&self) -> Vec2 {
+        self.0.translation
+    }
+
+    /// Angle of the local x axis in world space.
+    pub fn rotation(&self) -> f32 {
+        self.0.matrix2.x_axis.to_angle()
+    }
+
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        self.0.transform_point2(point)
+    }
+
+    /// Nearest `Transform`; any shear is lost.
+    pub fn to_transform(&self) -> Transform {
+        let (scale, rotation, position) = self.0.to_scale_angle_translation();
+        Transform { position, rotation, scale }
+    }
+
+    /// Interpolates towards `other`. The rotation is taken out before interpolating
+    /// the rest of the matrix, so spinning entities don't shrink between steps.
+    pub fn lerp(&self, other: &GlobalTransform, t: f32) -> GlobalTransform {
+        let (from_rotation, to_rotation) = (self.rotation(), other.rotation());
+        let rotation_delta = (to_rotation - from_rotation + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        let from_rest = Mat2::from_angle(-from_rotation) * self.0.matrix2;
+        let to_rest = Mat2::from_angle(-to_rotation) * other.0.matrix2;
+        let rest = from_rest + (to_rest - from_rest) * t;
+
+        GlobalTransform(Affine2::from_mat2_translation(
+            Mat2::from_angle(from_rotation + rotation_delta * t) * rest,
+            self.0.translation.lerp(other.0.translation, t),
+        ))
+    }
+
+    /// 3D model matrix for rendering, with `size` applied before this transform.
+    pub fn to_mat4(&self, size: Vec2) -> Mat4 {
+        let matrix = self.0.matrix2 * Mat2::from_diagonal(size);
+        Mat4::from_cols(
+            matrix.x_axis.extend(0.0).extend(0.0),
+            matrix.y_axis.extend(0.0).extend(0.0),
+            Vec4::Z,
+            self.0.translation.extend(0.0).extend(1.0),
+        )
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// The `GlobalTransform` as it was before the latest fixed simulation step, used to
+/// interpolate rendering between steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PreviousGlobalTransform(pub GlobalTransform);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Sprite {
     pub texture_name: String,
     pub width: f32,
     pub height: f32,
+    /// Sub-rectangle of the texture to sample as `(u, v, width, height)` in normalized coordinates.
+    pub uv_rect: Vec4,
+    /// RGBA colour multiplied with the sampled texel.
+    pub tint: Vec4,
+}
+
+/// How a `RigidBody` is moved by the simulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BodyType {
+    /// Moved by gravity, forces and contacts.
+    #[default]
+    Dynamic,
+    /// Moved only by its `velocity`; pushes dynamic bodies but is never pushed back.
+    Kinematic,
+    /// Never moves, like walls and floors.
+    Static,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RigidBody {
+    /// Linear velocity in pixels per second, read back from the simulation every step.
     pub velocity: Vec2,
+    /// Angular velocity in radians per second, counter-clockwise, read back like `velocity`.
+    pub angular_velocity: f32,
+    /// Constant acceleration in pixels per second squared, on top of gravity.
     pub acceleration: Vec2,
+    /// Total mass of the body. Ignored when its collider's material sets a density.
     pub mass: f32,
+    pub body_type: BodyType,
+    /// How quickly linear velocity decays on its own, per second.
+    pub linear_damping: f32,
+    /// How quickly angular velocity decays on its own, per second.
+    pub angular_damping: f32,
+    /// Multiplier applied to the world gravity for this body.
+    pub gravity_scale: f32,
+    /// Keeps the body upright regardless of contacts and torques.
+    pub lock_rotation: bool,
+}
+
+impl RigidBody {
+    pub fn new(velocity: Vec2, mass: f32) -> Self {
+        Self {
+            velocity,
+            angular_velocity: 0.0,
+            acceleration: Vec2::ZERO,
+            mass,
+            body_type: BodyType::Dynamic,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            gravity_scale: 1.0,
+            lock_rotation: false,
+        }
+    }
 }
 
+/// Force and torque applied to a dynamic `RigidBody` on every physics step until
+/// changed or removed, e.g. wind or thrusters. Forces are in mass units times pixels
+/// per second squared, torques in mass units times pixels squared per second squared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExternalForce {
+    pub force: Vec2,
+    pub torque: f32,
+}
+
+/// Impulses applied to a dynamic `RigidBody` at the next physics step, after which
+/// they are reset to zero. Useful for jumps and explosions.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExternalImpulse {
+    /// Applied at the centre of mass, in mass units times pixels per second.
+    pub impulse: Vec2,
+    /// In mass units times pixels squared per second.
+    pub torque_impulse: f32,
+    /// `(impulse, point)` pairs applied at world-space points in pixels, which can
+    /// also spin the body.
+    pub at_points: Vec<(Vec2, Vec2)>,
+}
+
+impl ExternalImpulse {
+    pub fn is_empty(&self) -> bool {
+        self.impulse == Vec2::ZERO && self.torque_impulse == 0.0 && self.at_points.is_empty()
+    }
+}
+
+/// How the friction or restitution of two touching colliders is combined. When the
+/// two colliders use different rules, the one listed last here wins.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombineRule {
+    #[default]
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+/// Surface and mass properties of a `Collider`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhysicsMaterial {
+    /// Coulomb friction coefficient; 0 is ice, around 1 is rubber.
+    pub friction: f32,
+    /// Bounciness; 0 absorbs impacts, 1 bounces back at full speed.
+    pub restitution: f32,
+    /// Mass per square meter. When set, the body's mass comes from the collider's
+    /// area instead of `RigidBody::mass`.
+    pub density: Option<f32>,
+    pub friction_combine: CombineRule,
+    pub restitution_combine: CombineRule,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self {
+            friction: 0.5,
+            restitution: 0.0,
+            density: None,
+            friction_combine: CombineRule::Average,
+            restitution_combine: CombineRule::Average,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Collider {
-    pub width: f32,
-    pub height: f32,
+    pub shape: ColliderShape,
     pub is_trigger: bool,
+    pub collision_filter: CollisionFilter,
+    pub material: PhysicsMaterial,
+}
+
+/// Constraint a `Joint` applies between its entity and its target.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JointKind {
+    /// The anchors share a pivot the bodies rotate freely around. Limits and motor act
+    /// on the entity's angle relative to the target, in radians.
+    Revolute,
+    /// The entity slides along `axis`, given in the target's local space. Limits and
+    /// motor act on the offset along the axis, in pixels.
+    Prismatic { axis: Vec2 },
+    /// The bodies are welded together.
+    Fixed,
+    /// The anchors stay at most `max_length` pixels apart. A motor acts on the distance.
+    Rope { max_length: f32 },
+    /// The anchors are pulled towards `rest_length` pixels apart.
+    Spring { rest_length: f32, stiffness: f32, damping: f32 },
+}
+
+/// Drives a joint's free axis towards a target position and/or velocity.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JointMotor {
+    pub target_position: f32,
+    pub target_velocity: f32,
+    pub stiffness: f32,
+    pub damping: f32,
+    pub max_force: f32,
+}
+
+impl JointMotor {
+    /// A motor spinning or sliding at `target_velocity`, applying at most `max_force`.
+    pub fn velocity(target_velocity: f32, max_force: f32) -> Self {
+        Self {
+            target_position: 0.0,
+            target_velocity,
+            stiffness: 0.0,
+            damping: 1.0,
+            max_force,
+        }
+    }
+
+    /// A motor pulling towards `target_position` like a damped spring.
+    pub fn position(target_position: f32, stiffness: f32, damping: f32) -> Self {
+        Self {
+            target_position,
+            target_velocity: 0.0,
+            stiffness,
+            damping,
+            max_force: f32::MAX,
+        }
+    }
+}
+
+/// Connects the entity's body to the body of `target`, or to a fixed point in the world
+/// when `target` is `None`. Both entities need a `RigidBody` (or `CharacterController`).
+/// For chains and ragdolls, put the joint on the child and target the parent.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Joint {
+    pub target: Option<Entity>,
+    pub kind: JointKind,
+    /// Attachment point in the entity's local space, in pixels.
+    pub anchor: Vec2,
+    /// Attachment point in the target's local space, or in world space without a target.
+    pub target_anchor: Vec2,
+    /// Allowed range of the free axis for revolute and prismatic joints.
+    pub limits: Option<[f32; 2]>,
+    /// Motor on the free axis for revolute, prismatic and rope joints.
+    pub motor: Option<JointMotor>,
+    /// Solves the joint in reduced coordinates as part of a multibody, which keeps long
+    /// chains from stretching. Falls back to an impulse joint if it would close a loop.
+    pub multibody: bool,
+    /// Whether the two connected bodies still collide with each other.
+    pub collide_connected: bool,
+}
+
+impl Joint {
+    pub fn new(target: Option<Entity>, kind: JointKind) -> Self {
+        Self {
+            target,
+            kind,
+            anchor: Vec2::ZERO,
+            target_anchor: Vec2::ZERO,
+            limits: None,
+            motor: None,
+            multibody: false,
+            collide_connected: false,
+        }
+    }
+}
+
+/// Moves a kinematic body by sweeping its `Collider` through the world instead of
+/// integrating velocity, so it slides along walls rather than tunnelling through them.
+/// Gravity is not applied; add it to `velocity` in gameplay code.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct CharacterController {
+    /// Desired movement in pixels per second, set by gameplay code before each step.
+    pub velocity: Vec2,
+    /// Direction the character considers "up" for slopes, steps and ground snapping.
+    pub up: Vec2,
+    /// Gap in pixels kept between the character and obstacles.
+    pub offset: f32,
+    /// Steepest slope, in radians, the character can walk up.
+    pub max_slope_climb_angle: f32,
+    /// Slopes steeper than this, in radians, make a grounded character slide down.
+    pub min_slope_slide_angle: f32,
+    /// Tallest step in pixels climbed automatically; `None` disables step climbing.
+    pub max_step_height: Option<f32>,
+    /// Minimum free width in pixels on top of a step for it to be climbed.
+    pub min_step_width: f32,
+    /// Keeps a grounded character attached to the ground when it drops by at most this
+    /// many pixels, e.g. walking down slopes; `None` disables snapping.
+    pub snap_to_ground: Option<f32>,
+    /// Contact state reported by the last physics step.
+    pub grounded: bool,
+    pub touching_ceiling: bool,
+    pub touching_wall: bool,
+    pub sliding_down_slope: bool,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            velocity: Vec2::ZERO,
+            up: Vec2::Y,
+            offset: 0.5,
+            max_slope_climb_angle: std::f32::consts::FRAC_PI_4,
+            min_slope_slide_angle: std::f32::consts::FRAC_PI_6,
+            max_step_height: Some(8.0),
+            min_step_width: 4.0,
+            snap_to_ground: Some(4.0),
+            grounded: false,
+            touching_ceiling: false,
+            touching_wall: false,
+            sliding_down_slope: false,
+        }
+    }
+}
+
+/// Marks a collider that only blocks character controllers landing on it from above;
+/// they can jump up through it and walk along its top.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct OneWayPlatform;
+
+/// Smallest zoom a `Camera2D` is rendered with; lower values, including zero and
+/// negative ones, are clamped to it.
+pub const MIN_CAMERA_ZOOM: f32 = 1e-3;
+
+/// A 2D orthographic camera. The renderer draws the scene once for every active camera
+/// into that camera's viewport, so several cameras can be used for split-screen.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Camera2D {
+    /// World-space point shown at the centre of the viewport.
+    pub position: Vec2,
+    /// Magnification factor; `2.0` shows half as much of the world as `1.0`.
+    pub zoom: f32,
+    /// Camera rotation in radians. The world appears rotated by the opposite angle.
+    pub rotation: f32,
+    /// Region of the render target as `(x, y, width, height)` in normalized coordinates,
+    /// measured from the top-left corner.
+    pub viewport: Vec4,
+    pub active: bool,
+}
+
+impl Camera2D {
+    pub fn new(position: Vec2) -> Self {
+        Self {
+            position,
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport: Vec4::new(0.0, 0.0, 1.0, 1.0),
+            active: true,
+        }
+    }
+
+    /// Pixel rectangle `(x, y, width, height)` of the viewport on a render target of
+    /// the given size, clamped to the target, or `None` if nothing of it is visible.
+    pub fn viewport_rect(&self, target_size: (u32, u32)) -> Option<(f32, f32, f32, f32)> {
+        let (target_width, target_height) = (target_size.0 as f32, target_size.1 as f32);
+        let x = (self.viewport.x * target_width).clamp(0.0, target_width);
+        let y = (self.viewport.y * target_height).clamp(0.0, target_height);
+        let width = (self.viewport.z * target_width).min(target_width - x);
+        let height = (self.viewport.w * target_height).min(target_height - y);
+        (width > 0.0 && height > 0.0).then_some((x, y, width, height))
+    }
+
+    /// Size of the clamped viewport in pixels on a render target of the given size.
+    pub fn viewport_size(&self, target_size: (u32, u32)) -> Vec2 {
+        self.viewport_rect(target_size)
+            .map_or(Vec2::ZERO, |(_, _, width, height)| Vec2::new(width, height))
+    }
+
+    /// `zoom`, kept above `MIN_CAMERA_ZOOM` so the projection stays finite.
+    pub fn effective_zoom(&self) -> f32 {
+        self.zoom.max(MIN_CAMERA_ZOOM)
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::from_rotation_z(-self.rotation)
+            * Mat4::from_translation(Vec3::new(-self.position.x, -self.position.y, 0.0))
+    }
+
+    pub fn projection_matrix(&self, target_size: (u32, u32)) -> Mat4 {
+        let half_extent = self.viewport_size(target_size) / (2.0 * self.effective_zoom());
+        Mat4::orthographic_lh(
+            -half_extent.x,
+            half_extent.x,
+            -half_extent.y,
+            half_extent.y,
+            -1.0,
+            1.0,
+        )
+    }
+
+    pub fn view_projection(&self, target_size: (u32, u32)) -> Mat4 {
+        self.projection_matrix(target_size) * self.view_matrix()
+    }
+
+    /// Converts a pixel position on the render target (origin top-left, Y down, as
+    /// reported by the window) into a world-space position seen through this camera.
+    pub fn screen_to_world(&self, screen_position: Vec2, target_size: (u32, u32)) -> Vec2 {
+        let Some((x, y, width, height)) = self.viewport_rect(target_size) else {
+            return self.position;
+        };
+        let (origin, size) = (Vec2::new(x, y), Vec2::new(width, height));
+        let ndc = Vec2::new(
+            (screen_position.x - origin.x) / size.x * 2.0 - 1.0,
+            1.0 - (screen_position.y - origin.y) / size.y * 2.0,
+        );
+        let world = self.view_projection(target_size).inverse() * Vec4::new(ndc.x, ndc.y, 0.0, 1.0);
+        Vec2::new(world.x, world.y)
+    }
+}
+
+/// Singleton values shared by systems, stored by type. Values are borrowed at runtime
+/// so systems given `&Resources` can still modify them; like a `RefCell`, borrowing a
+/// value mutably while it is already borrowed panics.
+#[derive(Default)]
+pub struct Resources {
+    values: HashMap<TypeId, RefCell<Box<dyn Any>>>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `value`, returning the previous value of the same type.
+    pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.values
+            .insert(TypeId::of::<T>(), RefCell::new(Box::new(value)))
+            .map(|previous| *previous.into_inner().downcast::<T>().unwrap())
+    }
+
+    pub fn remove<T: 'static>(&mut self) -> Option<T> {
+        self.values
+            .remove(&TypeId::of::<T>())
+            .map(|previous| *previous.into_inner().downcast::<T>().unwrap())
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<Ref<'_, T>> {
+        let cell = self.values.get(&TypeId::of::<T>())?;
+        let value = cell
+            .try_borrow()
+            .unwrap_or_else(|_| panic!("Resource {} is already borrowed mutably", type_name::<T>()));
+        Some(Ref::map(value, |value| value.downcast_ref::<T>().unwrap()))
+    }
+
+    pub fn get_mut<T: 'static>(&self) -> Option<RefMut<'_, T>> {
+        let cell = self.values.get(&TypeId::of::<T>())?;
+        let value = cell
+            .try_borrow_mut()
+            .unwrap_or_else(|_| panic!("Resource {} is already borrowed", type_name::<T>()));
+        Some(RefMut::map(value, |value| value.downcast_mut::<T>().unwrap()))
+    }
+}
+
+/// Pairs of colliders whose bounding boxes overlapped when `detect_collider_overlaps`
+/// last ran, each reported once with the lower entity first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColliderOverlaps(pub Vec<(Entity, Entity)>);
+
+/// Two-frame queue of events of one type, stored as a resource by
+/// `EcsWorld::add_event`. Events sent during a frame stay readable until the end of
+/// the next one, so every system gets a chance to see them whatever order it runs in.
+pub struct Events<T> {
+    /// Events sent during the previous frame, then during the current one.
+    previous: Vec<T>,
+    current: Vec<T>,
+    /// Id of the first event in `previous`. Ids keep counting up across frames so
+    /// readers can tell which events they've already seen.
+    first_id: usize,
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            first_id: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        self.current.extend(events);
+    }
+
+    /// Reader that will see every event still stored.
+    pub fn reader(&self) -> EventReader<T> {
+        EventReader {
+            next_id: self.first_id,
+            _event: PhantomData,
+        }
+    }
+
+    /// Reader that will only see events sent from now on.
+    pub fn reader_from_now(&self) -> EventReader<T> {
+        EventReader {
+            next_id: self.end_id(),
+            _event: PhantomData,
+        }
+    }
+
+    /// Every stored event, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(&self.current)
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Starts a new frame, dropping the events sent two frames ago. Called for every
+    /// added event type by the `update_events` system.
+    pub fn update(&mut self) {
+        self.first_id += self.previous.len();
+        std::mem::swap(&mut self.previous, &mut self.current);
+        self.current.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.first_id = self.end_id();
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    fn end_id(&self) -> usize {
+        self.first_id + self.len()
+    }
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Cursor into an `Events<T>` that remembers which events it has returned. Each
+/// system that wants to see every event keeps its own.
+pub struct EventReader<T> {
+    next_id: usize,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T> EventReader<T> {
+    /// Events sent since the last call, oldest first. Events dropped before this
+    /// reader got to them are skipped.
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> + use<'a, T> {
+        let skip = self.next_id.saturating_sub(events.first_id);
+        self.next_id = events.end_id();
+        events.iter().skip(skip)
+    }
+
+    /// Number of events `read` would return.
+    pub fn len(&self, events: &Events<T>) -> usize {
+        events.end_id() - self.next_id.max(events.first_id).min(events.end_id())
+    }
+
+    pub fn is_empty(&self, events: &Events<T>) -> bool {
+        self.len(events) == 0
+    }
+}
+
+/// Calls `Events::update` on every event type added with `EcsWorld::add_event`.
+#[derive(Default)]
+struct EventUpdaters(Vec<fn(&Resources)>);
+
+/// Sent by `detect_collider_overlaps` when two colliders' bounding boxes start or
+/// stop overlapping, with the lower entity first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlapEvent {
+    Started { a: Entity, b: Entity },
+    Ended { a: Entity, b: Entity },
+}
+
+type Command = Box<dyn FnOnce(&mut World) -> Result<(), String>>;
+
+/// Structural changes recorded while the world is borrowed, for example while
+/// iterating a query, and applied in order at the next flush. Stored as a resource;
+/// the schedule flushes it after every stage.
+#[derive(Default)]
+pub struct Commands {
+    commands: Vec<Command>,
 }
 
+impl Commands {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves an entity now and gives it `components` at the flush, so the entity
+    /// can be referred to by later commands straight away.
+    pub fn spawn(&mut self, world: &World, components: impl DynamicBundle + 'static) -> Entity {
+        let entity = world.reserve_entity();
+        self.add(move |world| {
+            world
+                .insert(entity, components)
+                .map_err(|e| format!("Failed to spawn {:?}: {}", entity, e))
+        });
+        entity
+    }
+
+    /// Adds or replaces components of an entity.
+    pub fn insert(&mut self, entity: Entity, components: impl DynamicBundle + 'static) {
+        self.add(move |world| {
+            world
+                .insert(entity, components)
+                .map_err(|e| format!("Failed to insert components into {:?}: {}", entity, e))
+        });
+    }
+
+    /// Removes the components in `T` from an entity. Fails if it lacks any of them.
+    pub fn remove<T: Bundle + 'static>(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world
+                .remove::<T>(entity)
+                .map(|_| ())
+                .map_err(|e| format!("Failed to remove {} from {:?}: {}", type_name::<T>(), entity, e))
+        });
+    }
+
+    pub fn despawn(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world
+                .despawn(entity)
+                .map_err(|e| format!("Failed to despawn {:?}: {}", entity, e))
+        });
+    }
+
+    /// Despawns an entity and its descendants. See `EcsWorld::despawn_recursive`.
+    pub fn despawn_recursive(&mut self, entity: Entity) {
+        self.add(move |world| despawn_recursive(world, entity));
+    }
+
+    /// Attaches `child` to `parent`. See `EcsWorld::set_parent`.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.add(move |world| set_parent(world, child, parent));
+    }
+
+    /// Queues any other change to the world.
+    pub fn add(&mut self, command: impl FnOnce(&mut World) -> Result<(), String> + 'static) {
+        self.commands.push(Box::new(command));
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Applies every queued command in order. A failing command doesn't stop the
+    /// rest; all failures are returned.
+    pub fn apply(&mut self, world: &mut World) -> Result<(), Vec<String>> {
+        let errors: Vec<String> = self
+            .commands
+            .drain(..)
+            .filter_map(|command| command(world).err())
+            .collect();
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+/// Sent when a queued command fails at a flush, with the reason.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandFailed(pub String);
+
 pub struct EcsWorld {
     pub world: World,
+    /// Shared values systems read and write alongside the `world`.
+    pub resources: Resources,
 }
 
 impl EcsWorld {
     pub fn new() -> Self {
-        Self {
+        let mut resources = Resources::new();
+        resources.insert(SpatialHash::default());
+        resources.insert(ColliderOverlaps::default());
+        resources.insert(EventUpdaters::default());
+        resources.insert(Commands::new());
+        resources.insert(ComponentRegistry::default());
+        resources.insert(Prefabs::new());
+        let mut ecs_world = Self {
             world: World::new(),
-        }
+            resources,
+        };
+        ecs_world.add_event::<OverlapEvent>();
+        ecs_world.add_event::<CommandFailed>();
+        ecs_world
     }
     
     pub fn create_entity(&mut self) -> Entity {
@@ -57,91 +800,523 @@ impl EcsWorld {
                 texture_name: texture_name.to_string(),
                 width,
                 height,
+                uv_rect: Vec4::new(0.0, 0.0, 1.0, 1.0),
+                tint: Vec4::ONE,
             },
         ))
     }
     
-    pub fn add_rigid_body(&mut self, entity: Entity, velocity: Vec2, mass: f32) {
-        if let Err(e) = self.world.insert_one(
-            entity,
-            RigidBody {
-                velocity,
-                acceleration: Vec2::ZERO,
-                mass,
-            },
-        ) {
-            println!("Failed to add RigidBody to entity: {:?}", e);
-        }
+    pub fn create_camera(&mut self, position: Vec2) -> Entity {
+        self.world.spawn((Camera2D::new(position),))
+    }
+    
+    /// Despawns an entity. Its physics body and collider, if any, are removed on the
+    /// next physics step.
+    pub fn despawn(&mut self, entity: Entity) -> Result<(), hecs::NoSuchEntity> {
+        self.world.despawn(entity)
+    }
+    
+    pub fn add_rigid_body(&mut self, entity: Entity, velocity: Vec2, mass: f32) -> Result<(), String> {
+        self.insert_component(entity, "RigidBody", RigidBody::new(velocity, mass))
     }
     
-    pub fn add_collider(&mut self, entity: Entity, width: f32, height: f32, is_trigger: bool) {
-        if let Err(e) = self.world.insert_one(
+    pub fn add_collider(&mut self, entity: Entity, width: f32, height: f32, is_trigger: bool) -> Result<(), String> {
+        self.add_collider_shape(entity, ColliderShape::cuboid(width, height), is_trigger)
+    }
+    
+    pub fn add_collider_shape(&mut self, entity: Entity, shape: ColliderShape, is_trigger: bool) -> Result<(), String> {
+        self.insert_component(
             entity,
+            "Collider",
             Collider {
-                width,
-                height,
+                shape,
                 is_trigger,
+                collision_filter: CollisionFilter::default(),
+                material: PhysicsMaterial::default(),
             },
-        ) {
-            println!("Failed to add Collider to entity: {:?}", e);
+        )
+    }
+    
+    pub fn velocity(&self, entity: Entity) -> Option<Vec2> {
+        self.world.get::<&RigidBody>(entity).ok().map(|rigid_body| rigid_body.velocity)
+    }
+
+    pub fn set_velocity(&mut self, entity: Entity, velocity: Vec2) -> Result<(), String> {
+        self.update_component(entity, "set velocity", |rigid_body: &mut RigidBody| rigid_body.velocity = velocity)
+    }
+
+    pub fn angular_velocity(&self, entity: Entity) -> Option<f32> {
+        self.world.get::<&RigidBody>(entity).ok().map(|rigid_body| rigid_body.angular_velocity)
+    }
+
+    pub fn set_angular_velocity(&mut self, entity: Entity, angular_velocity: f32) -> Result<(), String> {
+        self.update_component(entity, "set angular velocity", |rigid_body: &mut RigidBody| {
+            rigid_body.angular_velocity = angular_velocity
+        })
+    }
+
+    /// Sets the force and torque applied on every physics step; zero both to stop.
+    pub fn set_external_force(&mut self, entity: Entity, force: Vec2, torque: f32) -> Result<(), String> {
+        self.insert_component(entity, "ExternalForce", ExternalForce { force, torque })
+    }
+
+    /// Queues an impulse at the centre of mass for the next physics step.
+    pub fn apply_impulse(&mut self, entity: Entity, impulse: Vec2) -> Result<(), String> {
+        self.queue_impulse(entity, |queued| queued.impulse += impulse)
+    }
+
+    /// Queues an impulse at a world-space point for the next physics step.
+    pub fn apply_impulse_at_point(&mut self, entity: Entity, impulse: Vec2, point: Vec2) -> Result<(), String> {
+        self.queue_impulse(entity, |queued| queued.at_points.push((impulse, point)))
+    }
+
+    /// Queues an angular impulse for the next physics step.
+    pub fn apply_torque_impulse(&mut self, entity: Entity, torque_impulse: f32) -> Result<(), String> {
+        self.queue_impulse(entity, |queued| queued.torque_impulse += torque_impulse)
+    }
+
+    fn queue_impulse(&mut self, entity: Entity, add: impl FnOnce(&mut ExternalImpulse)) -> Result<(), String> {
+        if let Ok(mut queued) = self.world.get::<&mut ExternalImpulse>(entity) {
+            add(&mut queued);
+            return Ok(());
         }
+        let mut queued = ExternalImpulse::default();
+        add(&mut queued);
+        self.insert_component(entity, "ExternalImpulse", queued)
+    }
+
+    pub fn add_joint(&mut self, entity: Entity, joint: Joint) -> Result<(), String> {
+        self.insert_component(entity, "Joint", joint)
     }
     
-    pub fn update(&mut self) {
-        println!("--- Entity Positions ---");
-        for (entity, transform) in self.world.query::<&Transform>().iter() {
-            println!("Entity {:?} at position ({}, {}), scale: ({}, {})", 
-                entity, transform.position.x, transform.position.y,
-                transform.scale.x, transform.scale.y);
-        }
-        
-        for (_id, (rigid_body, transform)) in self.world.query_mut::<(&RigidBody, &mut Transform)>() {
-            transform.position += rigid_body.velocity;
-        }
-        
-        let mut collisions = Vec::new();
-        
-        let mut collider_entities = Vec::new();
-        for (entity, (transform, collider)) in self.world.query::<(&Transform, &Collider)>().iter() {
-            collider_entities.push((entity, transform.position, collider.width, collider.height));
-        }
-        
-        for i in 0..collider_entities.len() {
-            for j in (i + 1)..collider_entities.len() {
-                let (entity_a, pos_a, width_a, height_a) = collider_entities[i];
-                let (entity_b, pos_b, width_b, height_b) = collider_entities[j];
-                
-                let half_width_a = width_a / 2.0;
-                let half_height_a = height_a / 2.0;
-                let half_width_b = width_b / 2.0;
-                let half_height_b = height_b / 2.0;
-                
-                let min_x_a = pos_a.x - half_width_a;
-                let max_x_a = pos_a.x + half_width_a;
-                let min_y_a = pos_a.y - half_height_a;
-                let max_y_a = pos_a.y + half_height_a;
-                
-                let min_x_b = pos_b.x - half_width_b;
-                let max_x_b = pos_b.x + half_width_b;
-                let min_y_b = pos_b.y - half_height_b;
-                let max_y_b = pos_b.y + half_height_b;
-                
-                if max_x_a > min_x_b && min_x_a < max_x_b && max_y_a > min_y_b && min_y_a < max_y_b {
-                    collisions.push((entity_a, entity_b));
+    /// Makes the entity a kinematic character moved by the `PhysicsSystem`. It also
+    /// needs a `Collider`, which is swept through the world as the character's shape.
+    pub fn add_character_controller(&mut self, entity: Entity, controller: CharacterController) -> Result<(), String> {
+        self.insert_component(entity, "CharacterController", controller)
+    }
+    
+    /// Sets which collision layers the entity's collider belongs to and collides with.
+    pub fn set_collision_filter(&mut self, entity: Entity, filter: CollisionFilter) -> Result<(), String> {
+        self.update_component(entity, "set collision filter", |collider: &mut Collider| {
+            collider.collision_filter = filter
+        })
+    }
+    
+    pub fn set_physics_material(&mut self, entity: Entity, material: PhysicsMaterial) -> Result<(), String> {
+        self.update_component(entity, "set physics material", |collider: &mut Collider| collider.material = material)
+    }
+
+    fn insert_component(&mut self, entity: Entity, name: &str, component: impl Component) -> Result<(), String> {
+        self.world
+            .insert_one(entity, component)
+            .map_err(|e| format!("Failed to add {} to {:?}: {}", name, entity, e))
+    }
+
+    fn update_component<T: Component>(&mut self, entity: Entity, action: &str, update: impl FnOnce(&mut T)) -> Result<(), String> {
+        let mut component = self
+            .world
+            .get::<&mut T>(entity)
+            .map_err(|e| format!("Failed to {} on {:?}: {}", action, entity, e))?;
+        update(&mut component);
+        Ok(())
+    }
+
+    /// Attaches `child` to `parent`, detaching it from its previous parent. The child's
+    /// `Transform` is kept, so from now on it's an offset from the parent. Fails if
+    /// either entity doesn't exist or `parent` is `child` or one of its descendants.
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> Result<(), String> {
+        set_parent(&mut self.world, child, parent)
+    }
+
+    /// Detaches `child` from its parent, returning the parent it had. Its `Transform`
+    /// is kept, so it's now relative to the world.
+    pub fn remove_parent(&mut self, child: Entity) -> Option<Entity> {
+        remove_parent(&mut self.world, child)
+    }
+
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.world.get::<&Parent>(entity).ok().map(|parent| parent.0)
+    }
+
+    pub fn children(&self, entity: Entity) -> Vec<Entity> {
+        self.world.get::<&Children>(entity).map_or_else(|_| Vec::new(), |children| children.0.clone())
+    }
+
+    /// Despawns an entity together with everything attached to it, directly or not.
+    pub fn despawn_recursive(&mut self, entity: Entity) -> Result<(), String> {
+        despawn_recursive(&mut self.world, entity)
+    }
+
+    /// Commands queued for the next flush. The schedule flushes them after every stage.
+    pub fn commands(&self) -> RefMut<'_, Commands> {
+        self.resources.get_mut::<Commands>().unwrap()
+    }
+
+    /// Applies the queued commands now, returning why any of them failed.
+    pub fn apply_commands(&mut self) -> Result<(), Vec<String>> {
+        let mut commands = self.resources.get_mut::<Commands>().unwrap();
+        commands.apply(&mut self.world)
+    }
+
+    /// Lets scenes save and load components of type `T`, under `name` in scene files.
+    pub fn register_component<T: Component + Serialize + DeserializeOwned>(&mut self, name: &str) {
+        self.resources.get_mut::<ComponentRegistry>().unwrap().register::<T>(name);
+    }
+
+    /// Writes every entity with registered components to a `.ron` or `.json` file.
+    pub fn save_scene(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let registry = self.resources.get::<ComponentRegistry>().unwrap();
+        Scene::from_world(&self.world, &registry)?.save(path)
+    }
+
+    /// Spawns the entities of a `.ron` or `.json` scene file alongside the existing
+    /// ones, returning them in file order. Nothing is spawned if the file is invalid.
+    pub fn load_scene(&mut self, path: impl AsRef<Path>) -> Result<Vec<Entity>, String> {
+        let scene = Scene::load(path)?;
+        let registry = self.resources.get::<ComponentRegistry>().unwrap();
+        let entities = scene.spawn_into(&mut self.world, &registry)?;
+        propagate_global_transforms(&mut self.world);
+        Ok(entities)
+    }
+
+    /// Registers `prefab` under `name`, replacing any prefab registered under it.
+    pub fn register_prefab(&mut self, name: &str, prefab: Prefab) {
+        self.resources.get_mut::<Prefabs>().unwrap().insert(name, prefab);
+    }
+
+    /// Spawns the prefab called `name` with `overrides` applied, along with its child
+    /// prefabs, and returns the root entity.
+    pub fn spawn_prefab(&mut self, name: &str, overrides: Overrides) -> Result<Entity, String> {
+        let prefabs = self.resources.get::<Prefabs>().unwrap();
+        let registry = self.resources.get::<ComponentRegistry>().unwrap();
+        prefabs.spawn(&mut self.world, &registry, name, &overrides)
+    }
+
+    /// Records every entity's current `Transform` as its `PreviousTransform`. Called
+    /// before each fixed simulation step.
+    pub fn snapshot_transforms(&mut self) {
+        snapshot_transforms(&mut self.world, &self.resources);
+    }
+
+    /// Stores a resource, returning the previous value of the same type.
+    pub fn insert_resource<T: 'static>(&mut self, value: T) -> Option<T> {
+        self.resources.insert(value)
+    }
+
+    pub fn resource<T: 'static>(&self) -> Option<Ref<'_, T>> {
+        self.resources.get::<T>()
+    }
+
+    pub fn resource_mut<T: 'static>(&self) -> Option<RefMut<'_, T>> {
+        self.resources.get_mut::<T>()
+    }
+
+    /// Adds an `Events<T>` resource that the `update_events` system advances every
+    /// frame. Adding the same type again does nothing.
+    pub fn add_event<T: 'static>(&mut self) {
+        if self.resources.contains::<Events<T>>() {
+            return;
+        }
+        self.resources.insert(Events::<T>::new());
+        if let Some(mut updaters) = self.resources.get_mut::<EventUpdaters>() {
+            updaters.0.push(|resources| {
+                if let Some(mut events) = resources.get_mut::<Events<T>>() {
+                    events.update();
                 }
-            }
+            });
         }
-        
-        for (entity_a, entity_b) in collisions {
-            println!("Collision between entities {:?} and {:?}", entity_a, entity_b);
+    }
+
+    /// Sends an event of a type added with `add_event`.
+    pub fn send_event<T: 'static>(&self, event: T) {
+        match self.resources.get_mut::<Events<T>>() {
+            Some(mut events) => events.send(event),
+            None => log::warn!("Failed to send event: {} was never added", type_name::<T>()),
         }
     }
+
+    pub fn events<T: 'static>(&self) -> Option<Ref<'_, Events<T>>> {
+        self.resources.get::<Events<T>>()
+    }
+
+    /// Index of collider bounding boxes as of the last `detect_collider_overlaps`, for
+    /// radius and box queries that don't need rapier.
+    pub fn spatial_hash(&self) -> Ref<'_, SpatialHash> {
+        self.resources.get::<SpatialHash>().unwrap()
+    }
+
+    /// Changes the cell size of the spatial hash, ideally to about the size of a
+    /// typical collider. The index is rebuilt the next time overlaps are detected.
+    /// Fails, keeping the current index, unless `cell_size` is positive.
+    pub fn set_spatial_hash_cell_size(&mut self, cell_size: f32) -> Result<(), String> {
+        self.resources.insert(SpatialHash::new(cell_size)?);
+        Ok(())
+    }
+
+    /// Pairs of colliders whose bounding boxes overlapped at the last fixed step.
+    pub fn overlaps(&self) -> Ref<'_, [(Entity, Entity)]> {
+        Ref::map(self.resources.get::<ColliderOverlaps>().unwrap(), |overlaps| overlaps.0.as_slice())
+    }
     
-    pub fn get_renderables(&self) -> Vec<(Entity, Transform, Sprite)> {
+    /// Collects every sprite with its world-space transform interpolated `alpha` of the
+    /// way from the previous simulation step to the current one. Entities that haven't
+    /// been through `propagate_transforms` yet are placed by their `Transform`.
+    pub fn get_renderables(&self, alpha: f32) -> Vec<(Entity, GlobalTransform, Sprite)> {
         let mut renderables = Vec::new();
-        for (entity, (transform, sprite)) in self.world.query::<(&Transform, &Sprite)>().iter() {
-            renderables.push((entity, *transform, sprite.clone()));
+        for (entity, (transform, sprite, previous, global, previous_global)) in self.world
+            .query::<(
+                &Transform,
+                &Sprite,
+                Option<&PreviousTransform>,
+                Option<&GlobalTransform>,
+                Option<&PreviousGlobalTransform>,
+            )>()
+            .iter()
+        {
+            let global = match (global, previous_global, previous) {
+                (Some(global), Some(previous), _) => previous.0.lerp(global, alpha),
+                (Some(global), None, _) => *global,
+                (None, _, Some(previous)) => GlobalTransform::from_transform(&previous.0.lerp(transform, alpha)),
+                (None, _, None) => GlobalTransform::from_transform(transform),
+            };
+            renderables.push((entity, global, sprite.clone()));
         }
         renderables
     }
+    
+    pub fn get_active_cameras(&self) -> Vec<Camera2D> {
+        self.world
+            .query::<&Camera2D>()
+            .iter()
+            .filter(|(_, camera)| camera.active)
+            .map(|(_, camera)| *camera)
+            .collect()
+    }
+}
+
+impl Default for EcsWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Built-in system: records each `Transform` and `GlobalTransform` as the entity's
+/// `PreviousTransform` and `PreviousGlobalTransform` so rendering can interpolate.
+/// Runs first in `Stage::FixedUpdate`.
+pub fn snapshot_transforms(world: &mut World, _resources: &Resources) {
+    for (_entity, (transform, previous)) in world.query_mut::<(&Transform, &mut PreviousTransform)>() {
+        previous.0 = *transform;
+    }
+    for (_entity, (global, previous)) in world.query_mut::<(&GlobalTransform, &mut PreviousGlobalTransform)>() {
+        previous.0 = *global;
+    }
+
+    let missing_globals: Vec<(Entity, GlobalTransform)> = world
+        .query::<&GlobalTransform>()
+        .without::<&PreviousGlobalTransform>()
+        .iter()
+        .map(|(entity, global)| (entity, *global))
+        .collect();
+    for (entity, global) in missing_globals {
+        let _ = world.insert_one(entity, PreviousGlobalTransform(global));
+    }
+
+    let missing: Vec<(Entity, Transform)> = world
+        .query::<&Transform>()
+        .without::<&PreviousTransform>()
+        .iter()
+        .map(|(entity, transform)| (entity, *transform))
+        .collect();
+    for (entity, transform) in missing {
+        let _ = world.insert_one(entity, PreviousTransform(transform));
+    }
+}
+
+/// Built-in system: computes every `GlobalTransform` by composing each entity's
+/// `Transform` with its ancestors'. Entities whose `Parent` is gone or has no
+/// `Transform` are placed relative to the world.
+pub fn propagate_transforms(world: &mut World, _resources: &Resources) {
+    propagate_global_transforms(world);
+}
+
+/// The work of `propagate_transforms`, for callers without `Resources`.
+pub fn propagate_global_transforms(world: &mut World) {
+    let mut roots = Vec::new();
+    let mut children_of: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for (entity, parent) in world.query::<Option<&Parent>>().with::<&Transform>().iter() {
+        match parent {
+            Some(parent) if world.satisfies::<&Transform>(parent.0).unwrap_or(false) => {
+                children_of.entry(parent.0).or_default().push(entity);
+            }
+            _ => roots.push(entity),
+        }
+    }
+
+    // Walked iteratively so deep hierarchies can't overflow the stack. Entities caught
+    // in a parent cycle have no root and are left alone.
+    let mut globals = Vec::with_capacity(roots.len());
+    let mut pending: Vec<(Entity, GlobalTransform)> =
+        roots.into_iter().map(|entity| (entity, GlobalTransform::IDENTITY)).collect();
+    while let Some((entity, parent_global)) = pending.pop() {
+        let global = parent_global.mul_transform(&world.get::<&Transform>(entity).unwrap());
+        if let Some(children) = children_of.remove(&entity) {
+            pending.extend(children.into_iter().map(|child| (child, global)));
+        }
+        globals.push((entity, global));
+    }
+
+    for (entity, global) in globals {
+        if let Ok(mut current) = world.get::<&mut GlobalTransform>(entity) {
+            *current = global;
+        } else {
+            let _ = world.insert_one(entity, global);
+        }
+    }
+}
+
+/// Computes `entity`'s `GlobalTransform` from its own `Transform` and those of its
+/// ancestors, for code that can't wait for `propagate_transforms`. Like propagation,
+/// stops at an ancestor without a `Transform`. `None` if `entity` has no `Transform`
+/// or is caught in a parent cycle.
+pub fn compute_global_transform(world: &World, entity: Entity) -> Option<GlobalTransform> {
+    let mut chain = vec![*world.get::<&Transform>(entity).ok()?];
+    let mut next = world.get::<&Parent>(entity).ok().map(|parent| parent.0);
+    while let Some(ancestor) = next {
+        let Ok(transform) = world.get::<&Transform>(ancestor) else {
+            break;
+        };
+        // A chain longer than the world has entities must loop.
+        if chain.len() > world.len() as usize {
+            return None;
+        }
+        chain.push(*transform);
+        next = world.get::<&Parent>(ancestor).ok().map(|parent| parent.0);
+    }
+    Some(chain.iter().rev().fold(GlobalTransform::IDENTITY, |global, transform| global.mul_transform(transform)))
+}
+
+/// Attaches `child` to `parent`. See `EcsWorld::set_parent`.
+pub fn set_parent(world: &mut World, child: Entity, parent: Entity) -> Result<(), String> {
+    if !world.contains(child) || !world.contains(parent) {
+        return Err(format!("Cannot attach {:?} to {:?}: entity does not exist", child, parent));
+    }
+    let mut ancestor = Some(parent);
+    let mut depth = 0;
+    while let Some(entity) = ancestor {
+        if entity == child {
+            return Err(format!("Cannot attach {:?} to its own descendant {:?}", child, parent));
+        }
+        // A chain longer than the world has entities must loop.
+        depth += 1;
+        if depth > world.len() {
+            return Err(format!("Cannot attach {:?} to {:?}: its ancestors form a cycle", child, parent));
+        }
+        ancestor = world.get::<&Parent>(entity).ok().map(|parent| parent.0);
+    }
+
+    remove_parent(world, child);
+    let _ = world.insert_one(child, Parent(parent));
+    if let Ok(mut children) = world.get::<&mut Children>(parent) {
+        children.0.push(child);
+    } else {
+        let _ = world.insert_one(parent, Children(vec![child]));
+    }
+    Ok(())
+}
+
+/// Detaches `child` from its parent. See `EcsWorld::remove_parent`.
+pub fn remove_parent(world: &mut World, child: Entity) -> Option<Entity> {
+    let Parent(parent) = world.remove_one::<Parent>(child).ok()?;
+    if let Ok(mut children) = world.get::<&mut Children>(parent) {
+        children.0.retain(|entity| *entity != child);
+    }
+    Some(parent)
+}
+
+/// Despawns an entity and its descendants. See `EcsWorld::despawn_recursive`.
+pub fn despawn_recursive(world: &mut World, entity: Entity) -> Result<(), String> {
+    if !world.contains(entity) {
+        return Err(format!("Cannot despawn {:?}: entity does not exist", entity));
+    }
+    remove_parent(world, entity);
+
+    let mut pending = vec![entity];
+    while let Some(entity) = pending.pop() {
+        if let Ok(children) = world.get::<&Children>(entity) {
+            pending.extend(children.0.iter().copied());
+        }
+        let _ = world.despawn(entity);
+    }
+    Ok(())
+}
+
+/// Built-in flush point: applies the `Commands` resource's queued commands. The
+/// schedule calls it after every stage; register it as a system for flushes within a
+/// stage. Failures are logged and sent as `CommandFailed` events.
+pub fn apply_commands(world: &mut World, resources: &Resources) {
+    let Some(mut commands) = resources.get_mut::<Commands>() else {
+        return;
+    };
+    if commands.is_empty() {
+        return;
+    }
+    if let Err(errors) = commands.apply(world) {
+        let mut events = resources.get_mut::<Events<CommandFailed>>();
+        for error in errors {
+            log::warn!("{}", error);
+            if let Some(events) = &mut events {
+                events.send(CommandFailed(error));
+            }
+        }
+    }
+}
+
+/// Built-in system: starts a new frame for every `Events<T>` added with
+/// `EcsWorld::add_event`. Runs first in `Stage::PreUpdate`.
+pub fn update_events(_world: &mut World, resources: &Resources) {
+    if let Some(updaters) = resources.get::<EventUpdaters>() {
+        for update in &updaters.0 {
+            update(resources);
+        }
+    }
+}
+
+/// Built-in system: moves every `Collider`'s bounding box in the `SpatialHash`
+/// resource to where its `Transform` places it in the world, then stores the
+/// overlapping pairs in `ColliderOverlaps` and sends an `OverlapEvent` for each pair
+/// that started or stopped overlapping. Does nothing without both resources.
+pub fn detect_collider_overlaps(world: &mut World, resources: &Resources) {
+    let (Some(mut spatial_hash), Some(mut overlaps)) =
+        (resources.get_mut::<SpatialHash>(), resources.get_mut::<ColliderOverlaps>())
+    else {
+        return;
+    };
+
+    for (entity, (transform, collider, parent, global)) in world
+        .query::<(&Transform, &Collider, Option<&Parent>, Option<&GlobalTransform>)>()
+        .iter()
+    {
+        let (min, max) = match (parent, global) {
+            (Some(_), Some(global)) => collider.shape.bounding_box(&global.to_transform()),
+            _ => collider.shape.bounding_box(transform),
+        };
+        spatial_hash.insert(entity, min, max);
+    }
+    spatial_hash.retain(|entity| world.satisfies::<(&Transform, &Collider)>(entity).unwrap_or(false));
+    let previous = std::mem::replace(&mut overlaps.0, spatial_hash.overlapping_pairs());
+
+    let Some(mut events) = resources.get_mut::<Events<OverlapEvent>>() else {
+        return;
+    };
+    // Both lists are sorted, so a binary search finds pairs missing from the other.
+    let key = |(a, b): &(Entity, Entity)| (a.to_bits(), b.to_bits());
+    let missing_from = |pairs: &[(Entity, Entity)], pair: &(Entity, Entity)| {
+        pairs.binary_search_by_key(&key(pair), key).is_err()
+    };
+    for (a, b) in overlaps.0.iter().filter(|pair| missing_from(&previous, pair)) {
+        events.send(OverlapEvent::Started { a: *a, b: *b });
+    }
+    for (a, b) in previous.iter().filter(|pair| missing_from(&overlaps.0, pair)) {
+        events.send(OverlapEvent::Ended { a: *a, b: *b });
+    }
 }
\ No newline at end of file
@@ -1,22 +1,26 @@
-use crate::physics::PhysicsSystem;
+use crate::physics::{self, PhysicsEvent, PhysicsSystem};
 use crate::renderer::Renderer;
 use crate::{demo_scene::load_demo_scene, };
-use crate::input::InputManager;
+use crate::input::{InputEvent, InputManager};
 use crate::audio::AudioSystem;
-use crate::resources::ResourceManager;
-use crate::ecs::EcsWorld;
+use crate::resources::{self, AssetLoaded, ResourceManager};
+use crate::ecs::{self, EcsWorld};
+use crate::schedule::{Schedule, Stage, System};
+use crate::time::Time;
 
+use std::any::type_name;
+use std::cell::{Ref, RefMut};
 use std::sync::Arc;
 use std::mem::ManuallyDrop;
+use std::time::{Duration, Instant};
 use winit::{
-    application::ApplicationHandler, error::EventLoopError, event::WindowEvent, event_loop::{ActiveEventLoop, EventLoop}, window::{Window, WindowAttributes, WindowId}
+    application::ApplicationHandler, error::EventLoopError, event::{ElementState, WindowEvent}, event_loop::{ActiveEventLoop, EventLoop}, keyboard::{KeyCode, PhysicalKey}, window::{Window, WindowAttributes, WindowId}
 };
 
 struct EngineApp<'a> {
     engine: Option<Engine<'a>>,
     window_title: String,
     window_storage: Option<ManuallyDrop<Arc<Window>>>,
-    ecs_world: Option<EcsWorld>,
 }
 
 impl<'a> EngineApp<'a> {
@@ -25,7 +29,6 @@ impl<'a> EngineApp<'a> {
             engine: None,
             window_title: title,
             window_storage: None,
-            ecs_world: None,
         }
     }
 }
@@ -51,26 +54,10 @@ impl<'a> ApplicationHandler<()> for EngineApp<'a> {
                 Renderer::new(window_arc.clone())
             );
 
-            let white_pixels = create_solid_texture(255, 255, 255, 255, 32, 32);
-            let red_pixels = create_solid_texture(255, 0, 0, 255, 32, 32);
-            
-            renderer.load_texture("player", &white_pixels, 32, 32);
-            renderer.load_texture("enemy", &red_pixels, 32, 32);
+            load_default_textures(&mut renderer);
 
-            let mut world = EcsWorld::new();
-            
-            load_demo_scene(&mut world);
-
-            let engine_instance = Engine {
-                window_title: self.window_title.clone(),
-                window: Some(window),
-                renderer: Some(renderer),
-                input_manager: InputManager::new(),
-                physics_system: PhysicsSystem::new(),
-                audio_system: AudioSystem::new(),
-                resource_manager: ResourceManager::new(),
-                ecs_world: world,
-            };
+            let mut engine_instance = Engine::from_parts(self.window_title.clone(), Some(window), renderer, EcsWorld::new());
+            engine_instance.load_scene("demo", load_demo_scene);
             
             self.engine = Some(engine_instance);
 
@@ -86,21 +73,38 @@ impl<'a> ApplicationHandler<()> for EngineApp<'a> {
         event: WindowEvent,
     ) {
         if let Some(engine) = self.engine.as_mut() {
-            engine.input_manager.process_window_event(&event);
+            let input = engine.input_manager_mut().process_window_event(&event);
+            if let Some(input) = input {
+                engine.ecs_world.send_event(input);
+            }
 
             match event {
                 WindowEvent::CloseRequested => {
                     log::info!("Close requested. Exiting.");
                     event_loop.exit();
                 }
-                WindowEvent::Resized(new_size) => {
-                    if new_size.width > 0 && new_size.height > 0 {
-                        log::info!("Window resized to: {:?}", new_size);
-                        if let Some(renderer) = &mut engine.renderer {
-                            renderer.resize(new_size.width, new_size.height);
-                        }
-                        engine.window.as_ref().unwrap().request_redraw();
+                WindowEvent::Resized(new_size) if new_size.width > 0 && new_size.height > 0 => {
+                    log::info!("Window resized to: {:?}", new_size);
+                    if let Some(renderer) = &mut engine.renderer {
+                        renderer.resize(new_size.width, new_size.height);
                     }
+                    engine.ecs_world.send_event(WindowResized {
+                        width: new_size.width,
+                        height: new_size.height,
+                    });
+                    engine.window.as_ref().unwrap().request_redraw();
+                }
+                WindowEvent::Focused(focused) => {
+                    engine.ecs_world.send_event(WindowFocused { focused });
+                }
+                WindowEvent::KeyboardInput { event: key_event, .. }
+                    if key_event.state == ElementState::Pressed
+                        && !key_event.repeat
+                        && key_event.physical_key == PhysicalKey::Code(KeyCode::F1) =>
+                {
+                    let enabled = !engine.physics_debug_enabled();
+                    engine.set_physics_debug(enabled);
+                    log::info!("Physics debug overlay {}", if enabled { "enabled" } else { "disabled" });
                 }
                 WindowEvent::RedrawRequested => {
                     engine.update();
@@ -112,10 +116,10 @@ impl<'a> ApplicationHandler<()> for EngineApp<'a> {
     }
 
     fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
-        if let Some(engine) = &self.engine {
-            if let Some(window) = &engine.window {
-                window.request_redraw();
-            }
+        if let Some(engine) = &self.engine
+            && let Some(window) = &engine.window
+        {
+            window.request_redraw();
         }
     }
     
@@ -160,39 +164,71 @@ impl<'a> Drop for EngineApp<'a> {
     }
 }
 
+/// Sent when the window's inner size changes, in physical pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Sent when the window gains or loses keyboard focus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowFocused {
+    pub focused: bool,
+}
+
+/// Sent by `Engine::load_scene` once the new scene's entities have been spawned.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SceneChanged {
+    pub name: String,
+}
+
+/// Owns the window, renderer, world and schedule. The input, audio, asset and physics
+/// subsystems and the `Time` live in the world's resources, so systems can reach them.
 pub struct Engine<'a> {
     window_title: String,
     window: Option<Arc<Window>>,
     renderer: Option<Renderer<'a>>,
-    input_manager: InputManager,
-    physics_system: PhysicsSystem,
-    audio_system: AudioSystem,
-    resource_manager: ResourceManager,
     ecs_world: EcsWorld,
+    schedule: Schedule,
+    last_update: Option<Instant>,
 }
 impl<'a> Engine<'a> {
     pub async fn new(window: Arc<Window>, window_title: String) -> Self {
         let mut renderer = Renderer::new(window.clone()).await;
-
-    const WHITE_PIXEL: [u8; 4] = [255, 255, 255, 255];
-    renderer.load_texture("player", &WHITE_PIXEL, 1, 1);
-    renderer.load_texture("enemy",  &WHITE_PIXEL, 1, 1);
-        
-        let white_texture = create_solid_texture(255, 255, 255, 255, 32, 32);
-        let red_texture = create_solid_texture(255, 0, 0, 255, 32, 32);
-        
-        renderer.load_texture("player", &white_texture, 32, 32);
-        renderer.load_texture("enemy", &red_texture, 32, 32);
+        load_default_textures(&mut renderer);
         
+        Self::from_parts(window_title, Some(window), renderer, EcsWorld::new())
+    }
+
+    /// Creates an engine without a window that renders offscreen, for tests and tools.
+    pub async fn new_headless(width: u32, height: u32) -> Result<Self, String> {
+        let mut renderer = Renderer::new_headless(width, height).await?;
+        load_default_textures(&mut renderer);
+
+        Ok(Self::from_parts(String::new(), None, renderer, EcsWorld::new()))
+    }
+
+    fn from_parts(window_title: String, window: Option<Arc<Window>>, renderer: Renderer<'a>, mut ecs_world: EcsWorld) -> Self {
+        ecs_world.insert_resource(InputManager::new());
+        ecs_world.insert_resource(PhysicsSystem::new());
+        ecs_world.insert_resource(AudioSystem::new());
+        ecs_world.insert_resource(ResourceManager::new());
+        ecs_world.insert_resource(Time::default());
+        ecs_world.add_event::<WindowResized>();
+        ecs_world.add_event::<WindowFocused>();
+        ecs_world.add_event::<InputEvent>();
+        ecs_world.add_event::<PhysicsEvent>();
+        ecs_world.add_event::<AssetLoaded>();
+        ecs_world.add_event::<SceneChanged>();
+
         Self {
             window_title,
-            window: Some(window),
+            window,
             renderer: Some(renderer),
-            input_manager: InputManager::new(),
-            physics_system: PhysicsSystem::new(),
-            audio_system: AudioSystem::new(),
-            resource_manager: ResourceManager::new(),
-            ecs_world: EcsWorld::new(),
+            ecs_world,
+            schedule: default_schedule(),
+            last_update: None,
         }
     }
 
@@ -205,16 +241,196 @@ impl<'a> Engine<'a> {
         event_loop.run_app(&mut app)
     }
 
-    fn update(&mut self) {
-        self.ecs_world.update();
-        self.physics_system.update();
+    pub fn window_title(&self) -> &str {
+        &self.window_title
+    }
+
+    pub fn ecs_world(&self) -> &EcsWorld {
+        &self.ecs_world
+    }
+
+    pub fn ecs_world_mut(&mut self) -> &mut EcsWorld {
+        &mut self.ecs_world
+    }
+
+    /// Replaces every entity with the ones `load` spawns and sends `SceneChanged`.
+    /// Resources are kept.
+    pub fn load_scene(&mut self, name: &str, load: impl FnOnce(&mut EcsWorld)) {
+        self.ecs_world.world.clear();
+        load(&mut self.ecs_world);
+        self.ecs_world.send_event(SceneChanged { name: name.to_string() });
+    }
+
+    pub fn renderer(&self) -> Option<&Renderer<'a>> {
+        self.renderer.as_ref()
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+
+    /// Registers a system to run in `stage`. See `Schedule::add_system`.
+    pub fn add_system(&mut self, stage: Stage, system: System) -> Result<(), String> {
+        self.schedule.add_system(stage, system)
+    }
+
+    pub fn time(&self) -> Ref<'_, Time> {
+        self.subsystem()
+    }
+
+    pub fn time_mut(&mut self) -> RefMut<'_, Time> {
+        self.subsystem_mut()
+    }
+
+    pub fn input_manager(&self) -> Ref<'_, InputManager> {
+        self.subsystem()
     }
 
-    fn render(&mut self) {
+    pub fn input_manager_mut(&mut self) -> RefMut<'_, InputManager> {
+        self.subsystem_mut()
+    }
+
+    pub fn physics_system(&self) -> Ref<'_, PhysicsSystem> {
+        self.subsystem()
+    }
+
+    pub fn physics_system_mut(&mut self) -> RefMut<'_, PhysicsSystem> {
+        self.subsystem_mut()
+    }
+
+    pub fn audio_system(&self) -> Ref<'_, AudioSystem> {
+        self.subsystem()
+    }
+
+    pub fn audio_system_mut(&mut self) -> RefMut<'_, AudioSystem> {
+        self.subsystem_mut()
+    }
+
+    pub fn resource_manager(&self) -> Ref<'_, ResourceManager> {
+        self.subsystem()
+    }
+
+    pub fn resource_manager_mut(&mut self) -> RefMut<'_, ResourceManager> {
+        self.subsystem_mut()
+    }
+
+    /// Borrows one of the resources inserted by `from_parts`. Panics if it was removed.
+    fn subsystem<T: 'static>(&self) -> Ref<'_, T> {
+        self.ecs_world
+            .resource::<T>()
+            .unwrap_or_else(|| panic!("Engine resource {} was removed", type_name::<T>()))
+    }
+
+    /// Takes `&mut self` so overlapping borrows through the engine fail to compile
+    /// rather than panic.
+    fn subsystem_mut<T: 'static>(&mut self) -> RefMut<'_, T> {
+        self.ecs_world
+            .resource_mut::<T>()
+            .unwrap_or_else(|| panic!("Engine resource {} was removed", type_name::<T>()))
+    }
+
+    /// Advances the engine by the wall-clock time elapsed since the previous call.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let real_delta = self.last_update.map_or(Duration::ZERO, |last| now - last);
+        self.last_update = Some(now);
+        self.advance(real_delta);
+    }
+
+    /// Advances the engine by `real_delta`, running as many fixed simulation steps of
+    /// `PhysicsConfig::timestep` as the accumulated time allows. Deterministic, so tests
+    /// can drive it directly.
+    ///
+    /// Runs `Stage::PreUpdate`, then `Stage::FixedUpdate` (which steps physics) for
+    /// each fixed step, then `Stage::Update` and `Stage::PostUpdate`. Physics events
+    /// not drained since the previous frame are discarded before `PreUpdate`.
+    pub fn advance(&mut self, real_delta: Duration) {
+        let timestep = Duration::from_secs_f32(self.physics_system().config().timestep);
+        {
+            let mut time = self.time_mut();
+            if time.fixed_delta() != timestep {
+                time.set_fixed_delta(timestep);
+            }
+            time.begin_frame(real_delta);
+        }
+        self.physics_system_mut().clear_events();
+        self.run_stage(Stage::PreUpdate);
+        while self.time_mut().expend_fixed_step() {
+            self.run_stage(Stage::FixedUpdate);
+        }
+        self.run_stage(Stage::Update);
+        self.run_stage(Stage::PostUpdate);
+    }
+
+    fn run_stage(&mut self, stage: Stage) {
+        self.schedule.run_stage(stage, &mut self.ecs_world.world, &self.ecs_world.resources);
+    }
+
+    pub fn render(&mut self) {
+        self.run_stage(Stage::PreRender);
+        let alpha = self.time().interpolation_alpha();
+        if let Some(renderer) = &mut self.renderer {
+            if renderer.physics_debug_enabled()
+                && let Some(mut physics) = self.ecs_world.resource_mut::<PhysicsSystem>()
+            {
+                renderer.set_debug_lines(&physics.debug_lines());
+            }
+            renderer.render(&self.ecs_world, alpha);
+        }
+    }
+
+    /// Draws what the physics world looks like on top of the scene. F1 toggles it
+    /// in the windowed app.
+    pub fn set_physics_debug(&mut self, enabled: bool) {
         if let Some(renderer) = &mut self.renderer {
-            renderer.render(&self.ecs_world);
+            renderer.set_physics_debug(enabled);
         }
     }
+
+    pub fn physics_debug_enabled(&self) -> bool {
+        self.renderer.as_ref().is_some_and(Renderer::physics_debug_enabled)
+    }
+}
+
+/// Schedule with the engine's built-in systems. Events are advanced first thing in
+/// `Stage::PreUpdate`, so events sent during a frame last until the end of the next.
+/// Each fixed step snapshots and propagates transforms before physics moves them;
+/// they are propagated again before rendering to pick up `Stage::Update` changes.
+fn default_schedule() -> Schedule {
+    let mut schedule = Schedule::new();
+    let systems = [
+        (Stage::PreUpdate, System::new("update_events", ecs::update_events)),
+        (Stage::PreUpdate, System::new("send_asset_events", resources::send_asset_events).after("update_events")),
+        (Stage::FixedUpdate, System::new("snapshot_transforms", ecs::snapshot_transforms)),
+        (
+            Stage::FixedUpdate,
+            System::new("propagate_transforms", ecs::propagate_transforms).after("snapshot_transforms"),
+        ),
+        (
+            Stage::FixedUpdate,
+            System::new("detect_collider_overlaps", ecs::detect_collider_overlaps).after("propagate_transforms"),
+        ),
+        (Stage::FixedUpdate, System::new("physics", physics::step_physics).after("detect_collider_overlaps")),
+        (Stage::PreRender, System::new("propagate_render_transforms", ecs::propagate_transforms)),
+    ];
+    for (stage, system) in systems {
+        schedule
+            .add_system(stage, system)
+            .expect("built-in systems have unique names and no cycles");
+    }
+    schedule
+}
+
+fn load_default_textures(renderer: &mut Renderer) {
+    let white_pixels = create_solid_texture(255, 255, 255, 255, 32, 32);
+    let red_pixels = create_solid_texture(255, 0, 0, 255, 32, 32);
+
+    renderer.load_texture("player", &white_pixels, 32, 32);
+    renderer.load_texture("enemy", &red_pixels, 32, 32);
 }
 
 fn create_solid_texture(r: u8, g: u8, b: u8, a: u8, width: u32, height: u32) -> Vec<u8> {
@@ -0,0 +1,183 @@
+use crate::ecs::EcsWorld;
+use crate::engine::Engine;
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// Set this environment variable to write the rendered frame as the new reference
+/// instead of comparing against it.
+pub const UPDATE_GOLDEN_ENV: &str = "UPDATE_GOLDEN";
+
+/// Result of comparing a rendered frame against a reference image.
+pub struct ImageComparison {
+    pub mismatched_pixels: usize,
+    pub max_channel_difference: u8,
+    /// Mismatched pixels in red over a dimmed greyscale copy of the reference.
+    pub diff_image: RgbaImage,
+}
+
+impl ImageComparison {
+    pub fn matches(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compares two images of equal size. A pixel mismatches when any channel differs
+/// by more than `tolerance`.
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Result<ImageComparison, String> {
+    if actual.dimensions() != expected.dimensions() {
+        return Err(format!(
+            "Image size mismatch: got {:?}, expected {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        ));
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut max_channel_difference = 0;
+    let mut diff_image = RgbaImage::new(expected.width(), expected.height());
+
+    for ((actual_pixel, expected_pixel), diff_pixel) in actual
+        .pixels()
+        .zip(expected.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let difference = actual_pixel.0
+            .iter()
+            .zip(expected_pixel.0.iter())
+            .map(|(a, e)| a.abs_diff(*e))
+            .max()
+            .unwrap_or(0);
+        max_channel_difference = max_channel_difference.max(difference);
+
+        *diff_pixel = if difference > tolerance {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = expected_pixel.0;
+            let dimmed_luma = ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 3000) as u8;
+            Rgba([dimmed_luma, dimmed_luma, dimmed_luma, 255])
+        };
+    }
+
+    Ok(ImageComparison {
+        mismatched_pixels,
+        max_channel_difference,
+        diff_image,
+    })
+}
+
+/// Renders a scene headlessly after stepping the engine and compares the frame to a
+/// reference PNG stored as `<reference_dir>/<name>.png`. On failure the rendered frame
+/// and a diff image are written next to the reference as `<name>.actual.png` and
+/// `<name>.diff.png`.
+pub struct GoldenImageTest {
+    name: String,
+    reference_dir: PathBuf,
+    width: u32,
+    height: u32,
+    frames: u32,
+    tolerance: u8,
+    physics_debug: bool,
+}
+
+impl GoldenImageTest {
+    pub fn new(name: &str, reference_dir: impl AsRef<Path>) -> Self {
+        Self {
+            name: name.to_string(),
+            reference_dir: reference_dir.as_ref().to_path_buf(),
+            width: 640,
+            height: 360,
+            frames: 0,
+            tolerance: 2,
+            physics_debug: false,
+        }
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Number of fixed simulation steps to run before capturing the frame.
+    pub fn frames(mut self, frames: u32) -> Self {
+        self.frames = frames;
+        self
+    }
+
+    /// Maximum allowed per-channel difference for a pixel to still count as matching.
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Draws the physics debug overlay on top of the captured frame.
+    pub fn physics_debug(mut self, enabled: bool) -> Self {
+        self.physics_debug = enabled;
+        self
+    }
+
+    pub fn run(&self, load_scene: impl FnOnce(&mut EcsWorld)) -> Result<(), String> {
+        let mut engine = pollster::block_on(Engine::new_headless(self.width, self.height))?;
+        load_scene(engine.ecs_world_mut());
+        engine.set_physics_debug(self.physics_debug);
+
+        for _ in 0..self.frames {
+            let fixed_delta = engine.time().fixed_delta();
+            engine.advance(fixed_delta);
+        }
+        engine.render();
+
+        let actual = engine
+            .renderer()
+            .ok_or_else(|| "Headless engine has no renderer".to_string())?
+            .capture_frame()?;
+
+        let reference_path = self.reference_path("png");
+        if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            std::fs::create_dir_all(&self.reference_dir)
+                .map_err(|e| format!("Failed to create {}: {}", self.reference_dir.display(), e))?;
+            return save_png(&actual, &reference_path);
+        }
+        if !reference_path.exists() {
+            return Err(format!(
+                "Missing reference image {}; rerun with {}=1 to create it",
+                reference_path.display(),
+                UPDATE_GOLDEN_ENV
+            ));
+        }
+
+        let expected = image::open(&reference_path)
+            .map_err(|e| format!("Failed to load reference {}: {}", reference_path.display(), e))?
+            .to_rgba8();
+
+        let comparison = compare_images(&actual, &expected, self.tolerance)?;
+        if comparison.matches() {
+            return Ok(());
+        }
+
+        let actual_path = self.reference_path("actual.png");
+        let diff_path = self.reference_path("diff.png");
+        save_png(&actual, &actual_path)?;
+        save_png(&comparison.diff_image, &diff_path)?;
+
+        Err(format!(
+            "Golden image '{}' differs in {} pixels (max channel difference {}, tolerance {}); see {}",
+            self.name,
+            comparison.mismatched_pixels,
+            comparison.max_channel_difference,
+            self.tolerance,
+            diff_path.display()
+        ))
+    }
+
+    fn reference_path(&self, extension: &str) -> PathBuf {
+        self.reference_dir.join(format!("{}.{}", self.name, extension))
+    }
+}
+
+fn save_png(image: &RgbaImage, path: &Path) -> Result<(), String> {
+    image
+        .save_with_format(path, image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to save {}: {}", path.display(), e))
+}
@@ -2,6 +2,16 @@ use winit::event::WindowEvent;
 use std::collections::HashMap;
 use winit::event::{ElementState, MouseButton};
 
+/// A change in input state, sent by the engine as an `Events<InputEvent>` event.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputEvent {
+    KeyPressed(winit::keyboard::PhysicalKey),
+    KeyReleased(winit::keyboard::PhysicalKey),
+    MouseButtonPressed(MouseButton),
+    MouseButtonReleased(MouseButton),
+    CursorMoved { x: f32, y: f32 },
+}
+
 pub struct InputManager {
     keys_pressed: HashMap<winit::keyboard::PhysicalKey, bool>,
     mouse_buttons: HashMap<MouseButton, bool>,
@@ -17,22 +27,32 @@ impl InputManager {
         }
     }
     
-    pub fn process_window_event(&mut self, event: &WindowEvent) {
+    /// Updates the input state, returning what changed. Key repeats aren't changes.
+    pub fn process_window_event(&mut self, event: &WindowEvent) -> Option<InputEvent> {
         match event {
             WindowEvent::KeyboardInput { event, .. } => {
-                if let winit::event::KeyEvent { physical_key: key_code, .. } = event {
-                    let is_pressed = event.state == ElementState::Pressed;
-                    self.keys_pressed.insert(*key_code, is_pressed);
+                let is_pressed = event.state == ElementState::Pressed;
+                let was_pressed = self.keys_pressed.insert(event.physical_key, is_pressed).unwrap_or(false);
+                match (was_pressed, is_pressed) {
+                    (false, true) => Some(InputEvent::KeyPressed(event.physical_key)),
+                    (true, false) => Some(InputEvent::KeyReleased(event.physical_key)),
+                    _ => None,
                 }
             }
             WindowEvent::MouseInput { button, state, .. } => {
                 let is_pressed = *state == ElementState::Pressed;
                 self.mouse_buttons.insert(*button, is_pressed);
+                Some(if is_pressed {
+                    InputEvent::MouseButtonPressed(*button)
+                } else {
+                    InputEvent::MouseButtonReleased(*button)
+                })
             }
             WindowEvent::CursorMoved { position, .. } => {
                 self.mouse_position = (position.x as f32, position.y as f32);
+                Some(InputEvent::CursorMoved { x: self.mouse_position.0, y: self.mouse_position.1 })
             }
-            _ => {}
+            _ => None,
         }
     }
     
@@ -47,4 +67,10 @@ impl InputManager {
     pub fn get_mouse_position(&self) -> (f32, f32) {
         self.mouse_position
     }
+}
+
+impl Default for InputManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
\ No newline at end of file
@@ -0,0 +1,116 @@
+use rapier2d::prelude::{Group, InteractionGroups};
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of collision layers, one per bit of a rapier collision group.
+pub const MAX_COLLISION_LAYERS: usize = 32;
+
+/// A named collision layer registered with [`CollisionLayers`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionLayer(u8);
+
+impl CollisionLayer {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    fn bit(&self) -> u32 {
+        1 << self.0
+    }
+}
+
+/// Which layers a collider belongs to and which layers it collides with. Two colliders
+/// only interact if each one's memberships intersect the other's filter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollisionFilter {
+    pub memberships: u32,
+    pub filter: u32,
+}
+
+impl CollisionFilter {
+    /// Member of every layer and colliding with every layer.
+    pub const ALL: CollisionFilter = CollisionFilter { memberships: u32::MAX, filter: u32::MAX };
+    /// Interacts with nothing.
+    pub const NONE: CollisionFilter = CollisionFilter { memberships: 0, filter: 0 };
+
+    pub fn new(memberships: &[CollisionLayer], collides_with: &[CollisionLayer]) -> Self {
+        Self {
+            memberships: memberships.iter().fold(0, |bits, layer| bits | layer.bit()),
+            filter: collides_with.iter().fold(0, |bits, layer| bits | layer.bit()),
+        }
+    }
+
+    pub fn is_member_of(&self, layer: CollisionLayer) -> bool {
+        self.memberships & layer.bit() != 0
+    }
+
+    pub fn collides_with(&self, layer: CollisionLayer) -> bool {
+        self.filter & layer.bit() != 0
+    }
+
+    /// Whether two colliders with these filters interact.
+    pub fn interacts_with(&self, other: &CollisionFilter) -> bool {
+        self.memberships & other.filter != 0 && other.memberships & self.filter != 0
+    }
+
+    pub(crate) fn to_groups(self) -> InteractionGroups {
+        InteractionGroups::new(
+            Group::from_bits_retain(self.memberships),
+            Group::from_bits_retain(self.filter),
+        )
+    }
+}
+
+impl Default for CollisionFilter {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// Names for the collision layers used by a game, registered once at startup.
+#[derive(Clone, Debug, Default)]
+pub struct CollisionLayers {
+    names: Vec<String>,
+}
+
+impl CollisionLayers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a layer, or returns the existing one if `name` is already registered.
+    pub fn add(&mut self, name: &str) -> Result<CollisionLayer, String> {
+        if let Some(layer) = self.get(name) {
+            return Ok(layer);
+        }
+        if self.names.len() >= MAX_COLLISION_LAYERS {
+            return Err(format!(
+                "Cannot add collision layer '{}': all {} layers are in use",
+                name, MAX_COLLISION_LAYERS
+            ));
+        }
+        self.names.push(name.to_string());
+        Ok(CollisionLayer((self.names.len() - 1) as u8))
+    }
+
+    pub fn get(&self, name: &str) -> Option<CollisionLayer> {
+        self.names
+            .iter()
+            .position(|existing| existing == name)
+            .map(|index| CollisionLayer(index as u8))
+    }
+
+    pub fn name(&self, layer: CollisionLayer) -> Option<&str> {
+        self.names.get(layer.index()).map(String::as_str)
+    }
+
+    /// Builds a filter from layer names, failing on names that were never registered.
+    pub fn filter(&self, memberships: &[&str], collides_with: &[&str]) -> Result<CollisionFilter, String> {
+        let lookup = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| self.get(name).ok_or_else(|| format!("Unknown collision layer '{}'", name)))
+                .collect::<Result<Vec<_>, String>>()
+        };
+        Ok(CollisionFilter::new(&lookup(memberships)?, &lookup(collides_with)?))
+    }
+}
@@ -0,0 +1,16 @@
+pub mod renderer;
+pub mod ecs;
+pub mod input;
+pub mod audio;
+pub mod resources;
+pub mod engine;
+pub mod demo_scene;
+pub mod physics;
+pub mod golden;
+pub mod time;
+pub mod shapes;
+pub mod layers;
+pub mod spatial_hash;
+pub mod schedule;
+pub mod scene;
+pub mod prefab;
@@ -1,11 +1,4 @@
-mod renderer;
-mod ecs;
-mod input;
-mod audio;
-mod resources;
-mod engine;
-mod demo_scene;
-mod physics;
+use engine_2d::engine;
 
 fn main() {
     env_logger::init();
@@ -1,7 +1,427 @@
+use crate::ecs::{self, Transform};
+use crate::layers::{CollisionFilter, CollisionLayers};
+use crate::shapes::ColliderShape;
+use glam::{Vec2, Vec4};
+use hecs::{Entity, World};
+use rapier2d::control::{CharacterAutostep, CharacterLength, KinematicCharacterController};
+use rapier2d::parry::query::{self, ShapeCastOptions};
 use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+/// How far in pixels a body may sink into a one-way platform and still land on it.
+const ONE_WAY_PLATFORM_TOLERANCE: f32 = 2.0;
+
+/// Simulation settings. The ECS works in pixels while rapier works in meters;
+/// `pixels_per_meter` converts between the two whenever data crosses over.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PhysicsConfig {
+    pub pixels_per_meter: f32,
+    /// Gravity in meters per second squared. Y points up.
+    pub gravity: Vec2,
+    /// Constraint solver iterations per step; more is stiffer but slower.
+    pub solver_iterations: usize,
+    /// Continuous collision detection for dynamic bodies, so fast objects don't tunnel.
+    pub ccd_enabled: bool,
+    /// Length of one fixed simulation step in seconds.
+    pub timestep: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            pixels_per_meter: 100.0,
+            gravity: Vec2::new(0.0, -9.81),
+            solver_iterations: 4,
+            ccd_enabled: false,
+            timestep: 1.0 / 60.0,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    /// Checks that the settings can be simulated: `pixels_per_meter` and `timestep`
+    /// must be finite and greater than zero.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.pixels_per_meter.is_finite() && self.pixels_per_meter > 0.0) {
+            return Err(format!("pixels_per_meter must be positive, got {}", self.pixels_per_meter));
+        }
+        if !(self.timestep.is_finite() && self.timestep > 0.0) {
+            return Err(format!("timestep must be a positive number of seconds, got {}", self.timestep));
+        }
+        Ok(())
+    }
+}
+
+/// Contact and trigger notifications between ECS entities, produced by physics steps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhysicsEvent {
+    CollisionStarted { a: Entity, b: Entity },
+    CollisionEnded { a: Entity, b: Entity },
+    /// `other` started overlapping the trigger collider of `trigger`.
+    TriggerEntered { trigger: Entity, other: Entity },
+    TriggerExited { trigger: Entity, other: Entity },
+}
+
+/// Collects rapier's collision events during a step. Rapier may call it from several
+/// threads, hence the mutex.
+#[derive(Default)]
+struct CollisionEventCollector {
+    events: Mutex<Vec<CollisionEvent>>,
+}
+
+impl EventHandler for CollisionEventCollector {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        event: CollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        self.events.lock().unwrap().push(event);
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        _contact_pair: &ContactPair,
+        _total_force_magnitude: Real,
+    ) {
+    }
+}
+
+/// Decides whether two entities' colliders may touch. Runs during the physics step,
+/// possibly on several threads.
+pub type PairFilter = dyn Fn(Entity, Entity) -> bool + Send + Sync;
+
+/// Physics hooks applying one-way platforms to dynamic bodies and the user's
+/// `PairFilter` to every pair of colliders.
+struct PairFilterHooks<'a> {
+    one_way_platforms: &'a HashSet<ColliderHandle>,
+    pair_filter: Option<&'a PairFilter>,
+    /// `(platform, other)` collider pairs that were in contact during the previous step.
+    previous_landings: &'a HashSet<(ColliderHandle, ColliderHandle)>,
+    landings: Mutex<HashSet<(ColliderHandle, ColliderHandle)>>,
+    /// How far in meters a body may be inside a one-way platform and still land on it.
+    one_way_tolerance: f32,
+    dt: f32,
+}
+
+impl PairFilterHooks<'_> {
+    fn passes_pair_filter(&self, context: &PairFilterContext) -> bool {
+        let Some(pair_filter) = self.pair_filter else {
+            return true;
+        };
+        match (
+            entity_from_user_data(context.colliders[context.collider1].user_data),
+            entity_from_user_data(context.colliders[context.collider2].user_data),
+        ) {
+            (Some(first), Some(second)) => pair_filter(first, second),
+            _ => true,
+        }
+    }
+
+    fn passes_one_way_platforms(&self, context: &PairFilterContext) -> bool {
+        let (platform, other, other_body) = if self.one_way_platforms.contains(&context.collider1) {
+            (context.collider1, context.collider2, context.rigid_body2)
+        } else if self.one_way_platforms.contains(&context.collider2) {
+            (context.collider2, context.collider1, context.rigid_body1)
+        } else {
+            return true;
+        };
+
+        // Bodies pass through from below and only land when coming down from above
+        // the platform's top surface. Once landed they stay on it, even if the solver
+        // leaves them slightly sunk in.
+        let pair = (platform, other);
+        let landed = self.previous_landings.contains(&pair) || {
+            let platform = &context.colliders[platform];
+            let up = platform.rotation() * Vector::y();
+            let up = Vec2::new(up.x, up.y);
+            let upward_speed = other_body
+                .map(|handle| context.bodies[handle].linvel())
+                .map_or(0.0, |velocity| Vec2::new(velocity.x, velocity.y).dot(up));
+            // A falling body can sink up to one step's worth of movement before the
+            // contact is first seen.
+            let other_bottom = extent_along(&context.colliders[other].compute_aabb(), up).0;
+            let platform_top = extent_along(&platform.compute_aabb(), up).1;
+            upward_speed <= 0.0 && other_bottom >= platform_top - self.one_way_tolerance + upward_speed * self.dt
+        };
+        if landed {
+            self.landings.lock().unwrap().insert(pair);
+        }
+        landed
+    }
+}
+
+impl PhysicsHooks for PairFilterHooks<'_> {
+    fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<SolverFlags> {
+        (self.passes_pair_filter(context) && self.passes_one_way_platforms(context))
+            .then_some(SolverFlags::COMPUTE_IMPULSES)
+    }
+
+    /// One-way platforms don't affect triggers, only the user's filter does.
+    fn filter_intersection_pair(&self, context: &PairFilterContext) -> bool {
+        self.passes_pair_filter(context)
+    }
+}
+
+/// A coloured line segment in pixels, produced by [`PhysicsSystem::debug_lines`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DebugLine {
+    pub start: Vec2,
+    pub end: Vec2,
+    /// Linear RGBA colour.
+    pub color: Vec4,
+}
+
+const DYNAMIC_COLOR: Vec4 = Vec4::new(0.2, 1.0, 0.3, 1.0);
+const KINEMATIC_COLOR: Vec4 = Vec4::new(0.3, 0.6, 1.0, 1.0);
+const STATIC_COLOR: Vec4 = Vec4::new(0.7, 0.7, 0.7, 1.0);
+const TRIGGER_COLOR: Vec4 = Vec4::new(1.0, 0.85, 0.1, 1.0);
+const AABB_COLOR: Vec4 = Vec4::new(0.9, 0.3, 0.9, 0.5);
+/// Sleeping bodies are drawn at this fraction of their colour's brightness.
+const SLEEPING_BRIGHTNESS: f32 = 0.4;
+
+/// Collects the lines of rapier's debug render, converted to pixels and coloured by
+/// body type: dynamic, kinematic and static bodies, triggers and sleeping bodies each
+/// get their own colour. Contacts, joints and body axes keep rapier's style colours.
+struct DebugLineCollector<'a> {
+    bodies: &'a RigidBodySet,
+    pixels_per_meter: f32,
+    lines: Vec<DebugLine>,
+}
+
+impl DebugLineCollector<'_> {
+    fn color(&self, object: &DebugRenderObject, suggested: DebugColor) -> Vec4 {
+        match object {
+            DebugRenderObject::Collider(_, collider) => {
+                let body = collider.parent().and_then(|handle| self.bodies.get(handle));
+                let color = if collider.is_sensor() {
+                    TRIGGER_COLOR
+                } else {
+                    match body.map(|body| body.body_type()) {
+                        Some(RigidBodyType::Dynamic) => DYNAMIC_COLOR,
+                        Some(RigidBodyType::KinematicPositionBased | RigidBodyType::KinematicVelocityBased) => {
+                            KINEMATIC_COLOR
+                        }
+                        Some(RigidBodyType::Fixed) | None => STATIC_COLOR,
+                    }
+                };
+                if body.is_some_and(|body| body.is_sleeping()) {
+                    (color.truncate() * SLEEPING_BRIGHTNESS).extend(color.w)
+                } else {
+                    color
+                }
+            }
+            DebugRenderObject::ColliderAabb(..) => AABB_COLOR,
+            _ => hsla_to_rgba(suggested),
+        }
+    }
+}
+
+impl DebugRenderBackend for DebugLineCollector<'_> {
+    fn draw_line(&mut self, object: DebugRenderObject, a: Point<Real>, b: Point<Real>, color: DebugColor) {
+        let color = self.color(&object, color);
+        self.lines.push(DebugLine {
+            start: Vec2::new(a.x, a.y) * self.pixels_per_meter,
+            end: Vec2::new(b.x, b.y) * self.pixels_per_meter,
+            color,
+        });
+    }
+}
+
+/// rapier's debug style colours are HSLA with the hue in degrees.
+fn hsla_to_rgba([hue, saturation, lightness, alpha]: DebugColor) -> Vec4 {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    Vec4::new(r + m, g + m, b + m, alpha)
+}
+
+/// Restricts which colliders a scene query can hit.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PhysicsQueryFilter {
+    /// Only colliders whose collision layers interact with this filter are hit.
+    pub collision_filter: Option<CollisionFilter>,
+    /// Colliders belonging to this entity are ignored, e.g. the entity casting the ray.
+    pub exclude_entity: Option<Entity>,
+    pub exclude_sensors: bool,
+    pub exclude_solids: bool,
+}
+
+impl PhysicsQueryFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn collision_filter(mut self, collision_filter: CollisionFilter) -> Self {
+        self.collision_filter = Some(collision_filter);
+        self
+    }
+
+    pub fn exclude_entity(mut self, entity: Entity) -> Self {
+        self.exclude_entity = Some(entity);
+        self
+    }
+
+    pub fn exclude_sensors(mut self) -> Self {
+        self.exclude_sensors = true;
+        self
+    }
+
+    pub fn exclude_solids(mut self) -> Self {
+        self.exclude_solids = true;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayHit {
+    pub entity: Entity,
+    pub point: Vec2,
+    pub normal: Vec2,
+    /// Distance along the ray to the hit point.
+    pub distance: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapeHit {
+    pub entity: Entity,
+    /// Distance travelled by the cast shape before touching `entity`.
+    pub distance: f32,
+    /// Contact point on the hit collider.
+    pub point: Vec2,
+    /// Surface normal of the hit collider at `point`.
+    pub normal: Vec2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointProjectionHit {
+    pub entity: Entity,
+    /// Closest point on the collider's surface, or the query point itself if inside.
+    pub point: Vec2,
+    pub is_inside: bool,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct ColliderOwner {
+    entity: Entity,
+    is_trigger: bool,
+}
+
+/// Rapier objects created for one ECS entity, plus the pose and velocity last written
+/// back to the ECS so edits made by gameplay code between steps can be detected.
+#[derive(Serialize, Deserialize)]
+struct PhysicsLink {
+    body: Option<RigidBodyHandle>,
+    collider: Option<ColliderHandle>,
+    /// Whether the entity had a `Collider` when linked, even if its shape was invalid.
+    has_collider: bool,
+    /// Whether the body is the kinematic body of a `CharacterController`.
+    is_character: bool,
+    synced_position: Vec2,
+    synced_rotation: f32,
+    synced_velocity: Vec2,
+    synced_angular_velocity: f32,
+    /// World-space scale the collider shape was built at.
+    synced_scale: Vec2,
+    /// `Collider::shape` and `Collider::is_trigger` the collider was built from.
+    synced_shape: Option<ColliderShape>,
+    synced_trigger: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum JointLinkHandle {
+    Impulse(ImpulseJointHandle),
+    Multibody(MultibodyJointHandle),
+}
+
+/// Rapier joint created for an entity's `Joint`, with the bodies and settings it was
+/// built from so changes can be applied in place or trigger a rebuild.
+#[derive(Serialize, Deserialize)]
+struct JointLink {
+    handle: JointLinkHandle,
+    bodies: (RigidBodyHandle, RigidBodyHandle),
+    joint: ecs::Joint,
+}
+
+/// ECS components the simulation writes to, saved alongside the rapier state so a
+/// restored snapshot puts the world back exactly as it was.
+#[derive(Serialize, Deserialize)]
+struct MirroredComponents {
+    entity: Entity,
+    transform: Option<Transform>,
+    previous_transform: Option<ecs::PreviousTransform>,
+    rigid_body: Option<ecs::RigidBody>,
+    character: Option<ecs::CharacterController>,
+    impulse: Option<ecs::ExternalImpulse>,
+}
+
+/// Everything `PhysicsSystem::snapshot` writes, borrowed so taking a snapshot doesn't
+/// clone the simulation. Field order must match `SimulationState`.
+#[derive(Serialize)]
+struct SimulationStateRef<'a> {
+    config: &'a PhysicsConfig,
+    integration_parameters: &'a IntegrationParameters,
+    island_manager: &'a IslandManager,
+    broad_phase: &'a DefaultBroadPhase,
+    narrow_phase: &'a NarrowPhase,
+    rigid_body_set: &'a RigidBodySet,
+    collider_set: &'a ColliderSet,
+    joint_set: &'a ImpulseJointSet,
+    multibody_joint_set: &'a MultibodyJointSet,
+    ccd_solver: &'a CCDSolver,
+    query_pipeline: &'a QueryPipeline,
+    links: &'a HashMap<Entity, PhysicsLink>,
+    joints: &'a HashMap<Entity, JointLink>,
+    one_way_platforms: &'a HashSet<ColliderHandle>,
+    one_way_landings: &'a HashSet<(ColliderHandle, ColliderHandle)>,
+    world_body: &'a Option<RigidBodyHandle>,
+    collider_owners: &'a HashMap<ColliderHandle, ColliderOwner>,
+    removed_colliders: &'a Vec<ColliderHandle>,
+    components: Vec<MirroredComponents>,
+}
+
+/// Owned counterpart of `SimulationStateRef`, read back by `PhysicsSystem::restore`.
+#[derive(Deserialize)]
+struct SimulationState {
+    config: PhysicsConfig,
+    integration_parameters: IntegrationParameters,
+    island_manager: IslandManager,
+    broad_phase: DefaultBroadPhase,
+    narrow_phase: NarrowPhase,
+    rigid_body_set: RigidBodySet,
+    collider_set: ColliderSet,
+    joint_set: ImpulseJointSet,
+    multibody_joint_set: MultibodyJointSet,
+    ccd_solver: CCDSolver,
+    query_pipeline: QueryPipeline,
+    links: HashMap<Entity, PhysicsLink>,
+    joints: HashMap<Entity, JointLink>,
+    one_way_platforms: HashSet<ColliderHandle>,
+    one_way_landings: HashSet<(ColliderHandle, ColliderHandle)>,
+    world_body: Option<RigidBodyHandle>,
+    collider_owners: HashMap<ColliderHandle, ColliderOwner>,
+    removed_colliders: Vec<ColliderHandle>,
+    components: Vec<MirroredComponents>,
+}
 
 pub struct PhysicsSystem {
-    gravity: Vector<Real>,
+    config: PhysicsConfig,
     physics_pipeline: PhysicsPipeline,
     island_manager: IslandManager,
     broad_phase: DefaultBroadPhase,
@@ -9,14 +429,42 @@ pub struct PhysicsSystem {
     rigid_body_set: RigidBodySet,
     collider_set: ColliderSet,
     joint_set: ImpulseJointSet,
+    multibody_joint_set: MultibodyJointSet,
     ccd_solver: CCDSolver,
     integration_parameters: IntegrationParameters,
+    query_pipeline: QueryPipeline,
+    links: HashMap<Entity, PhysicsLink>,
+    joints: HashMap<Entity, JointLink>,
+    /// Joints that were missing a body at the last step, so the warning about them is
+    /// logged once rather than every step.
+    joints_missing_bodies: HashSet<Entity>,
+    collision_layers: CollisionLayers,
+    one_way_platforms: HashSet<ColliderHandle>,
+    one_way_landings: HashSet<(ColliderHandle, ColliderHandle)>,
+    pair_filter: Option<Box<PairFilter>>,
+    /// Fixed body that joints without a target entity are attached to.
+    world_body: Option<RigidBodyHandle>,
+    collider_owners: HashMap<ColliderHandle, ColliderOwner>,
+    removed_colliders: Vec<ColliderHandle>,
+    event_collector: CollisionEventCollector,
+    events: Vec<PhysicsEvent>,
+    debug_render_pipeline: DebugRenderPipeline,
 }
 
 impl PhysicsSystem {
     pub fn new() -> Self {
-        Self {
-            gravity: vector![0.0, -9.81],
+        Self::from_config(PhysicsConfig::default())
+    }
+
+    /// Fails if `config` doesn't pass `PhysicsConfig::validate`.
+    pub fn with_config(config: PhysicsConfig) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Self::from_config(config))
+    }
+
+    fn from_config(config: PhysicsConfig) -> Self {
+        let mut physics = Self {
+            config,
             physics_pipeline: PhysicsPipeline::new(),
             island_manager: IslandManager::new(),
             broad_phase: DefaultBroadPhase::new(),
@@ -24,14 +472,137 @@ impl PhysicsSystem {
             rigid_body_set: RigidBodySet::new(),
             collider_set: ColliderSet::new(),
             joint_set: ImpulseJointSet::new(),
+            multibody_joint_set: MultibodyJointSet::new(),
             ccd_solver: CCDSolver::new(),
             integration_parameters: IntegrationParameters::default(),
+            query_pipeline: QueryPipeline::new(),
+            links: HashMap::new(),
+            joints: HashMap::new(),
+            joints_missing_bodies: HashSet::new(),
+            collision_layers: CollisionLayers::new(),
+            one_way_platforms: HashSet::new(),
+            one_way_landings: HashSet::new(),
+            pair_filter: None,
+            world_body: None,
+            collider_owners: HashMap::new(),
+            removed_colliders: Vec::new(),
+            event_collector: CollisionEventCollector::default(),
+            events: Vec::new(),
+            debug_render_pipeline: DebugRenderPipeline::new(
+                DebugRenderStyle::default(),
+                DebugRenderMode::COLLIDER_SHAPES
+                    | DebugRenderMode::COLLIDER_AABBS
+                    | DebugRenderMode::JOINTS
+                    | DebugRenderMode::CONTACTS,
+            ),
+        };
+        physics.apply_config(config);
+        physics
+    }
+
+    /// Named layers for `CollisionFilter`s, registered once at startup.
+    pub fn collision_layers(&self) -> &CollisionLayers {
+        &self.collision_layers
+    }
+
+    pub fn collision_layers_mut(&mut self) -> &mut CollisionLayers {
+        &mut self.collision_layers
+    }
+
+    /// Installs a filter consulted for every pair of colliders whose layers allow them
+    /// to interact; returning `false` lets them pass through each other.
+    pub fn set_pair_filter(&mut self, filter: impl Fn(Entity, Entity) -> bool + Send + Sync + 'static) {
+        self.pair_filter = Some(Box::new(filter));
+    }
+
+    pub fn clear_pair_filter(&mut self) {
+        self.pair_filter = None;
+    }
+
+    pub fn config(&self) -> &PhysicsConfig {
+        &self.config
+    }
+
+    /// Applies new settings. Changing `pixels_per_meter` rebuilds the simulation from
+    /// the ECS on the next step, which resets angular velocities and joint warm-starting.
+    /// Settings that fail `PhysicsConfig::validate` are rejected and nothing changes.
+    pub fn set_config(&mut self, config: PhysicsConfig) -> Result<(), String> {
+        config.validate()?;
+        self.apply_config(config);
+        Ok(())
+    }
+
+    fn apply_config(&mut self, config: PhysicsConfig) {
+        if config.pixels_per_meter != self.config.pixels_per_meter {
+            self.reset_simulation();
+        }
+        if config.ccd_enabled != self.config.ccd_enabled {
+            for (_, body) in self.rigid_body_set.iter_mut() {
+                if body.is_dynamic() {
+                    body.enable_ccd(config.ccd_enabled);
+                }
+            }
         }
+        self.integration_parameters.num_solver_iterations =
+            NonZeroUsize::new(config.solver_iterations).unwrap_or(NonZeroUsize::MIN);
+        self.config = config;
+    }
+
+    /// Drops every rapier object; they are recreated from the ECS on the next update.
+    fn reset_simulation(&mut self) {
+        self.island_manager = IslandManager::new();
+        self.broad_phase = DefaultBroadPhase::new();
+        self.narrow_phase = NarrowPhase::new();
+        self.rigid_body_set = RigidBodySet::new();
+        self.collider_set = ColliderSet::new();
+        self.joint_set = ImpulseJointSet::new();
+        self.multibody_joint_set = MultibodyJointSet::new();
+        self.ccd_solver = CCDSolver::new();
+        self.query_pipeline = QueryPipeline::new();
+        self.links.clear();
+        self.joints.clear();
+        self.one_way_platforms.clear();
+        self.one_way_landings.clear();
+        self.world_body = None;
+        self.collider_owners.clear();
+        self.removed_colliders.clear();
+    }
+
+    fn to_meters(&self, pixels: Vec2) -> Vector<Real> {
+        to_vector(pixels / self.config.pixels_per_meter)
+    }
+
+    fn to_pixels(&self, meters: &Vector<Real>) -> Vec2 {
+        Vec2::new(meters.x, meters.y) * self.config.pixels_per_meter
+    }
+
+    fn isometry(&self, position: Vec2, rotation: f32) -> Isometry<Real> {
+        Isometry::new(self.to_meters(position), rotation)
     }
-    
-    pub fn update(&mut self) {
+
+    /// Advances the simulation by `dt` seconds. Entities with a `RigidBody` and/or
+    /// `Collider` are mirrored into rapier first, and the resulting poses and velocities
+    /// are written back into their `Transform` and `RigidBody` afterwards.
+    /// `CharacterController`s are moved before the step and report their contacts.
+    /// `ExternalForce`s and `RigidBody::acceleration` act during the step, and queued
+    /// `ExternalImpulse`s are applied and cleared.
+    pub fn update(&mut self, world: &mut World, dt: f32) {
+        self.sync_to_physics(world);
+        self.sync_joints(world);
+        self.apply_external_forces(world);
+        self.move_characters(world, dt);
+
+        self.integration_parameters.dt = dt;
+        let hooks = PairFilterHooks {
+            one_way_platforms: &self.one_way_platforms,
+            pair_filter: self.pair_filter.as_deref(),
+            previous_landings: &self.one_way_landings,
+            landings: Mutex::new(HashSet::new()),
+            one_way_tolerance: ONE_WAY_PLATFORM_TOLERANCE / self.config.pixels_per_meter,
+            dt,
+        };
         self.physics_pipeline.step(
-            &self.gravity,
+            &to_vector(self.config.gravity),
             &self.integration_parameters,
             &mut self.island_manager,
             &mut self.broad_phase,
@@ -39,11 +610,1192 @@ impl PhysicsSystem {
             &mut self.rigid_body_set,
             &mut self.collider_set,
             &mut self.joint_set,
-            &mut MultibodyJointSet::new(),
+            &mut self.multibody_joint_set,
             &mut self.ccd_solver,
-            None,
-            &(),
-            &(),
+            Some(&mut self.query_pipeline),
+            &hooks,
+            &self.event_collector,
         );
+        self.one_way_landings = hooks.landings.into_inner().unwrap();
+
+        self.collect_events();
+        self.sync_from_physics(world);
+    }
+
+    /// Outlines of every collider and its bounding box, contact points with their
+    /// normals and joint anchors, as of the last `update`. Meant for a debug overlay.
+    pub fn debug_lines(&mut self) -> Vec<DebugLine> {
+        let mut collector = DebugLineCollector {
+            bodies: &self.rigid_body_set,
+            pixels_per_meter: self.config.pixels_per_meter,
+            lines: Vec::new(),
+        };
+        self.debug_render_pipeline.render(
+            &mut collector,
+            &self.rigid_body_set,
+            &self.collider_set,
+            &self.joint_set,
+            &self.multibody_joint_set,
+            &self.narrow_phase,
+        );
+        collector.lines
+    }
+
+    /// Serializes the complete simulation, together with the `Transform`, `RigidBody`,
+    /// `CharacterController` and `ExternalImpulse` of every simulated entity, so it can be rolled back
+    /// with [`PhysicsSystem::restore`]. Collision layers and the pair filter are
+    /// settings rather than state and are not included.
+    pub fn snapshot(&self, world: &World) -> Result<Vec<u8>, String> {
+        let mut entities: Vec<Entity> = self.links.keys().copied().collect();
+        entities.sort_by_key(|entity| entity.to_bits());
+        let components = entities
+            .into_iter()
+            .map(|entity| MirroredComponents {
+                entity,
+                transform: world.get::<&Transform>(entity).ok().map(|transform| *transform),
+                previous_transform: world
+                    .get::<&ecs::PreviousTransform>(entity)
+                    .ok()
+                    .map(|previous| *previous),
+                rigid_body: world.get::<&ecs::RigidBody>(entity).ok().map(|body| *body),
+                character: world
+                    .get::<&ecs::CharacterController>(entity)
+                    .ok()
+                    .map(|character| *character),
+                impulse: world.get::<&ecs::ExternalImpulse>(entity).ok().map(|impulse| (*impulse).clone()),
+            })
+            .collect();
+
+        let state = SimulationStateRef {
+            config: &self.config,
+            integration_parameters: &self.integration_parameters,
+            island_manager: &self.island_manager,
+            broad_phase: &self.broad_phase,
+            narrow_phase: &self.narrow_phase,
+            rigid_body_set: &self.rigid_body_set,
+            collider_set: &self.collider_set,
+            joint_set: &self.joint_set,
+            multibody_joint_set: &self.multibody_joint_set,
+            ccd_solver: &self.ccd_solver,
+            query_pipeline: &self.query_pipeline,
+            links: &self.links,
+            joints: &self.joints,
+            one_way_platforms: &self.one_way_platforms,
+            one_way_landings: &self.one_way_landings,
+            world_body: &self.world_body,
+            collider_owners: &self.collider_owners,
+            removed_colliders: &self.removed_colliders,
+            components,
+        };
+        bincode::serialize(&state).map_err(|e| format!("Failed to serialize physics snapshot: {}", e))
+    }
+
+    /// Puts the simulation and the mirrored ECS components back to a snapshot taken
+    /// with [`PhysicsSystem::snapshot`]. Stepping afterwards with the same inputs gives
+    /// bit-identical results. Entities despawned since the snapshot are skipped;
+    /// entities spawned since are picked up again on the next `update`.
+    pub fn restore(&mut self, world: &mut World, snapshot: &[u8]) -> Result<(), String> {
+        let state: SimulationState = bincode::deserialize(snapshot)
+            .map_err(|e| format!("Failed to deserialize physics snapshot: {}", e))?;
+        state
+            .config
+            .validate()
+            .map_err(|e| format!("Physics snapshot has invalid settings: {}", e))?;
+
+        for components in state.components {
+            let entity = components.entity;
+            if !world.contains(entity) {
+                log::warn!("Entity {:?} from the physics snapshot no longer exists", entity);
+                continue;
+            }
+            if let Some(transform) = components.transform {
+                let _ = world.insert_one(entity, transform);
+            }
+            if let Some(previous_transform) = components.previous_transform {
+                let _ = world.insert_one(entity, previous_transform);
+            }
+            if let Some(rigid_body) = components.rigid_body {
+                let _ = world.insert_one(entity, rigid_body);
+            }
+            if let Some(character) = components.character {
+                let _ = world.insert_one(entity, character);
+            }
+            if let Some(impulse) = components.impulse {
+                let _ = world.insert_one(entity, impulse);
+            }
+        }
+
+        self.config = state.config;
+        self.integration_parameters = state.integration_parameters;
+        self.island_manager = state.island_manager;
+        self.broad_phase = state.broad_phase;
+        self.narrow_phase = state.narrow_phase;
+        self.rigid_body_set = state.rigid_body_set;
+        self.collider_set = state.collider_set;
+        self.joint_set = state.joint_set;
+        self.multibody_joint_set = state.multibody_joint_set;
+        self.ccd_solver = state.ccd_solver;
+        self.query_pipeline = state.query_pipeline;
+        self.links = state.links;
+        self.joints = state.joints;
+        self.one_way_platforms = state.one_way_platforms;
+        self.one_way_landings = state.one_way_landings;
+        self.world_body = state.world_body;
+        self.collider_owners = state.collider_owners;
+        self.removed_colliders = state.removed_colliders;
+        self.event_collector = CollisionEventCollector::default();
+        self.events.clear();
+        Ok(())
+    }
+
+    /// Takes all collision and trigger events produced since the last call.
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, PhysicsEvent> {
+        self.events.drain(..)
+    }
+
+    pub fn events(&self) -> &[PhysicsEvent] {
+        &self.events
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+
+    fn collect_events(&mut self) {
+        let raw_events = std::mem::take(&mut *self.event_collector.events.lock().unwrap());
+        for event in raw_events {
+            let (Some(first), Some(second)) = (
+                self.collider_owners.get(&event.collider1()),
+                self.collider_owners.get(&event.collider2()),
+            ) else {
+                continue;
+            };
+
+            let physics_event = if first.is_trigger || second.is_trigger {
+                let (trigger, other) = if first.is_trigger {
+                    (first.entity, second.entity)
+                } else {
+                    (second.entity, first.entity)
+                };
+                if event.started() {
+                    PhysicsEvent::TriggerEntered { trigger, other }
+                } else {
+                    PhysicsEvent::TriggerExited { trigger, other }
+                }
+            } else if event.started() {
+                PhysicsEvent::CollisionStarted { a: first.entity, b: second.entity }
+            } else {
+                PhysicsEvent::CollisionEnded { a: first.entity, b: second.entity }
+            };
+            self.events.push(physics_event);
+        }
+
+        // Colliders removed before this step are kept until their final `Stopped`
+        // events have been translated above.
+        for handle in self.removed_colliders.drain(..) {
+            self.collider_owners.remove(&handle);
+        }
     }
-}
\ No newline at end of file
+
+    pub fn body_handle(&self, entity: Entity) -> Option<RigidBodyHandle> {
+        self.links.get(&entity).and_then(|link| link.body)
+    }
+
+    pub fn impulse_joint_handle(&self, entity: Entity) -> Option<ImpulseJointHandle> {
+        match self.joints.get(&entity)?.handle {
+            JointLinkHandle::Impulse(handle) => Some(handle),
+            JointLinkHandle::Multibody(_) => None,
+        }
+    }
+
+    pub fn multibody_joint_handle(&self, entity: Entity) -> Option<MultibodyJointHandle> {
+        match self.joints.get(&entity)?.handle {
+            JointLinkHandle::Multibody(handle) => Some(handle),
+            JointLinkHandle::Impulse(_) => None,
+        }
+    }
+
+    pub fn collider_handle(&self, entity: Entity) -> Option<ColliderHandle> {
+        self.links.get(&entity).and_then(|link| link.collider)
+    }
+
+    /// The ECS entity a rapier collider was created for.
+    pub fn collider_entity(&self, handle: ColliderHandle) -> Option<Entity> {
+        self.collider_owners.get(&handle).map(|owner| owner.entity)
+    }
+
+    /// The ECS entity a rapier body was created for.
+    pub fn body_entity(&self, handle: RigidBodyHandle) -> Option<Entity> {
+        self.rigid_body_set
+            .get(handle)
+            .and_then(|body| entity_from_user_data(body.user_data))
+    }
+
+    /// Casts a ray and returns the closest hit. Queries see the world as of the last
+    /// `update`; entities spawned since then are not included yet.
+    pub fn cast_ray(&self, origin: Vec2, direction: Vec2, max_distance: f32, filter: PhysicsQueryFilter) -> Option<RayHit> {
+        let ray = Ray::new(self.to_meters(origin).into(), to_vector(direction.normalize_or_zero()));
+        let (handle, intersection) = self.query_pipeline.cast_ray_and_get_normal(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &ray,
+            max_distance / self.config.pixels_per_meter,
+            true,
+            self.rapier_filter(&filter),
+        )?;
+        self.ray_hit(&ray, handle, intersection)
+    }
+
+    /// Every collider hit by the ray, sorted by distance.
+    pub fn cast_ray_all(&self, origin: Vec2, direction: Vec2, max_distance: f32, filter: PhysicsQueryFilter) -> Vec<RayHit> {
+        let ray = Ray::new(self.to_meters(origin).into(), to_vector(direction.normalize_or_zero()));
+        let mut hits = Vec::new();
+        self.query_pipeline.intersections_with_ray(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &ray,
+            max_distance / self.config.pixels_per_meter,
+            true,
+            self.rapier_filter(&filter),
+            |handle, intersection| {
+                hits.extend(self.ray_hit(&ray, handle, intersection));
+                true
+            },
+        );
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    /// Sweeps `shape` from `position` along `direction` and returns the first collider
+    /// it would touch within `max_distance`.
+    pub fn cast_shape(
+        &self,
+        shape: &ColliderShape,
+        position: Vec2,
+        rotation: f32,
+        direction: Vec2,
+        max_distance: f32,
+        filter: PhysicsQueryFilter,
+    ) -> Option<ShapeHit> {
+        let shape = shape.to_shared_shape(Vec2::splat(1.0 / self.config.pixels_per_meter)).ok()?;
+        let start = self.isometry(position, rotation);
+        let direction = to_vector(direction.normalize_or_zero());
+        let (handle, hit) = self.query_pipeline.cast_shape(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &start,
+            &direction,
+            &*shape,
+            ShapeCastOptions::with_max_time_of_impact(max_distance / self.config.pixels_per_meter),
+            self.rapier_filter(&filter),
+        )?;
+
+        // The query pipeline treats the whole collider set as the first shape, placed at
+        // the origin, so its witness point and normal are already in world space. The
+        // cast stops within an absolute tolerance in meters, which is visible once scaled
+        // to pixels, so the point is taken from an exact contact at the hit pose instead.
+        let hit_pose = Isometry::from_parts((start.translation.vector + direction * hit.time_of_impact).into(), start.rotation);
+        let point = self
+            .collider_set
+            .get(handle)
+            .and_then(|collider| {
+                let prediction = 1.0 / self.config.pixels_per_meter;
+                query::contact(collider.position(), collider.shape(), &hit_pose, &*shape, prediction).ok().flatten()
+            })
+            .map_or(hit.witness1, |contact| contact.point1);
+        Some(ShapeHit {
+            entity: self.collider_entity(handle)?,
+            distance: hit.time_of_impact * self.config.pixels_per_meter,
+            point: self.to_pixels(&point.coords),
+            normal: Vec2::new(hit.normal1.x, hit.normal1.y),
+        })
+    }
+
+    /// Finds the collider closest to `point`.
+    pub fn project_point(&self, point: Vec2, filter: PhysicsQueryFilter) -> Option<PointProjectionHit> {
+        let (handle, projection) = self.query_pipeline.project_point(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &self.to_meters(point).into(),
+            true,
+            self.rapier_filter(&filter),
+        )?;
+        Some(PointProjectionHit {
+            entity: self.collider_entity(handle)?,
+            point: self.to_pixels(&projection.point.coords),
+            is_inside: projection.is_inside,
+        })
+    }
+
+    /// All entities whose colliders contain `point`, e.g. for mouse picking.
+    pub fn entities_at_point(&self, point: Vec2, filter: PhysicsQueryFilter) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        self.query_pipeline.intersections_with_point(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &self.to_meters(point).into(),
+            self.rapier_filter(&filter),
+            |handle| {
+                entities.extend(self.collider_entity(handle));
+                true
+            },
+        );
+        entities
+    }
+
+    /// All entities whose collider bounding boxes overlap the box from `min` to `max`.
+    pub fn entities_in_aabb(&self, min: Vec2, max: Vec2, filter: PhysicsQueryFilter) -> Vec<Entity> {
+        let aabb = Aabb::new(self.to_meters(min).into(), self.to_meters(max).into());
+        let rapier_filter = self.rapier_filter(&filter);
+        let mut entities = Vec::new();
+        self.query_pipeline.colliders_with_aabb_intersecting_aabb(&aabb, |handle| {
+            if let Some(collider) = self.collider_set.get(*handle)
+                && rapier_filter.test(&self.rigid_body_set, *handle, collider)
+                && collider.compute_aabb().intersects(&aabb)
+            {
+                entities.extend(self.collider_entity(*handle));
+            }
+            true
+        });
+        entities
+    }
+
+    /// All entities whose colliders overlap `shape` placed at `position` and `rotation`.
+    pub fn entities_intersecting_shape(
+        &self,
+        shape: &ColliderShape,
+        position: Vec2,
+        rotation: f32,
+        filter: PhysicsQueryFilter,
+    ) -> Vec<Entity> {
+        let Ok(shape) = shape.to_shared_shape(Vec2::splat(1.0 / self.config.pixels_per_meter)) else {
+            return Vec::new();
+        };
+        let mut entities = Vec::new();
+        self.query_pipeline.intersections_with_shape(
+            &self.rigid_body_set,
+            &self.collider_set,
+            &self.isometry(position, rotation),
+            &*shape,
+            self.rapier_filter(&filter),
+            |handle| {
+                entities.extend(self.collider_entity(handle));
+                true
+            },
+        );
+        entities
+    }
+
+    fn rapier_filter(&self, filter: &PhysicsQueryFilter) -> QueryFilter<'static> {
+        let mut rapier_filter = QueryFilter::new();
+        rapier_filter.groups = filter.collision_filter.map(CollisionFilter::to_groups);
+        if filter.exclude_sensors {
+            rapier_filter = rapier_filter.exclude_sensors();
+        }
+        if filter.exclude_solids {
+            rapier_filter = rapier_filter.exclude_solids();
+        }
+        if let Some(link) = filter.exclude_entity.and_then(|entity| self.links.get(&entity)) {
+            rapier_filter.exclude_collider = link.collider;
+            rapier_filter.exclude_rigid_body = link.body;
+        }
+        rapier_filter
+    }
+
+    fn ray_hit(&self, ray: &Ray, handle: ColliderHandle, intersection: RayIntersection) -> Option<RayHit> {
+        let point = ray.point_at(intersection.time_of_impact);
+        Some(RayHit {
+            entity: self.collider_entity(handle)?,
+            point: self.to_pixels(&point.coords),
+            normal: Vec2::new(intersection.normal.x, intersection.normal.y),
+            distance: intersection.time_of_impact * self.config.pixels_per_meter,
+        })
+    }
+
+    fn sync_to_physics(&mut self, world: &World) {
+        self.remove_stale_links(world);
+
+        let mut query = world.query::<(
+            &Transform,
+            Option<&ecs::RigidBody>,
+            Option<&ecs::Collider>,
+            Option<&ecs::CharacterController>,
+            Option<&ecs::Parent>,
+            Option<&ecs::GlobalTransform>,
+        )>();
+        // Visited in entity order rather than archetype order, so rapier handles, and
+        // with them the order contact pairs are reported in, don't change when an
+        // entity gains or loses an unrelated component.
+        let mut entries: Vec<_> = query.iter().collect();
+        entries.sort_unstable_by_key(|(entity, _)| entity.id());
+        for (entity, (transform, rigid_body, collider, character, parent, global)) in entries {
+            if rigid_body.is_none() && collider.is_none() && character.is_none() {
+                continue;
+            }
+
+            // Attached entities are simulated where their `GlobalTransform` puts them.
+            // For the rest it equals their `Transform`, which is read directly so poses
+            // round-trip exactly.
+            let world_transform = match (parent, global) {
+                (Some(_), Some(global)) => global.to_transform(),
+                _ => *transform,
+            };
+            let transform = &world_transform;
+            if let Some(collider) = collider
+                && let Some(link) = self.links.get(&entity)
+                && (link.synced_shape.as_ref() != Some(&collider.shape)
+                    || link.synced_trigger != collider.is_trigger
+                    || link.synced_scale != transform.scale)
+            {
+                self.rebuild_collider(entity, transform, rigid_body, collider);
+            }
+            let isometry = self.isometry(transform.position, transform.rotation);
+            let velocity = rigid_body.map(|rigid_body| self.to_meters(rigid_body.velocity));
+            match self.links.get_mut(&entity) {
+                Some(link) => {
+                    if let Some(collider) = collider
+                        && let Some(rapier_collider) = link.collider.and_then(|handle| self.collider_set.get_mut(handle))
+                    {
+                        let groups = collider.collision_filter.to_groups();
+                        if rapier_collider.collision_groups() != groups {
+                            rapier_collider.set_collision_groups(groups);
+                        }
+                        if apply_material(rapier_collider, &collider.material, rigid_body)
+                            && let Some(body) = link.body.and_then(|handle| self.rigid_body_set.get_mut(handle))
+                        {
+                            body.recompute_mass_properties_from_colliders(&self.collider_set);
+                        }
+                    }
+                    let Some(body) = link.body.and_then(|handle| self.rigid_body_set.get_mut(handle)) else {
+                        // Standalone colliders are only ever moved from the ECS side.
+                        if transform.position != link.synced_position || transform.rotation != link.synced_rotation {
+                            if let Some(rapier_collider) = link.collider.and_then(|handle| self.collider_set.get_mut(handle)) {
+                                rapier_collider.set_position(isometry);
+                            }
+                            link.synced_position = transform.position;
+                            link.synced_rotation = transform.rotation;
+                        }
+                        continue;
+                    };
+                    if let Some(rigid_body) = rigid_body
+                        && !link.is_character
+                    {
+                        apply_body_settings(body, rigid_body, self.config.ccd_enabled);
+                        // Without a collider to carry it, the mass lives on the body itself.
+                        if !link.has_collider {
+                            body.set_additional_mass(rigid_body.mass, true);
+                        }
+                    }
+                    if transform.position != link.synced_position || transform.rotation != link.synced_rotation {
+                        body.set_position(isometry, true);
+                    }
+                    if let Some(rigid_body) = rigid_body
+                        && let Some(velocity) = velocity
+                        && rigid_body.velocity != link.synced_velocity
+                    {
+                        body.set_linvel(velocity, true);
+                    }
+                    if let Some(rigid_body) = rigid_body
+                        && rigid_body.angular_velocity != link.synced_angular_velocity
+                    {
+                        body.set_angvel(rigid_body.angular_velocity, true);
+                    }
+                }
+                None => {
+                    let link = self.create_link(entity, transform, rigid_body, collider, character.is_some());
+                    self.links.insert(entity, link);
+                }
+            }
+        }
+
+        self.one_way_platforms = world
+            .query::<&ecs::OneWayPlatform>()
+            .iter()
+            .filter_map(|(entity, _)| self.links.get(&entity).and_then(|link| link.collider))
+            .collect();
+    }
+
+    /// Hands `ExternalForce`s, `RigidBody::acceleration` and queued `ExternalImpulse`s
+    /// to rapier, converted from pixel units. Only dynamic bodies are affected; impulses
+    /// queued on other bodies, or on entities without one, are dropped.
+    fn apply_external_forces(&mut self, world: &mut World) {
+        let pixels_per_meter = self.config.pixels_per_meter;
+        let square_meters = pixels_per_meter * pixels_per_meter;
+        for (entity, (rigid_body, external_force, impulse)) in world.query_mut::<(
+            &ecs::RigidBody,
+            Option<&ecs::ExternalForce>,
+            Option<&mut ecs::ExternalImpulse>,
+        )>() {
+            let Some(body) = self
+                .links
+                .get(&entity)
+                .and_then(|link| link.body)
+                .and_then(|handle| self.rigid_body_set.get_mut(handle))
+            else {
+                if let Some(impulse) = impulse {
+                    *impulse = ecs::ExternalImpulse::default();
+                }
+                continue;
+            };
+
+            if body.is_dynamic() {
+                let external_force = external_force.copied().unwrap_or_default();
+                let force = to_vector((external_force.force + rigid_body.acceleration * body.mass()) / pixels_per_meter);
+                if body.user_force() != force {
+                    body.reset_forces(false);
+                    body.add_force(force, true);
+                }
+                let torque = external_force.torque / square_meters;
+                if body.user_torque() != torque {
+                    body.reset_torques(false);
+                    body.add_torque(torque, true);
+                }
+            }
+
+            if let Some(impulse) = impulse
+                && !impulse.is_empty()
+            {
+                body.apply_impulse(to_vector(impulse.impulse / pixels_per_meter), true);
+                body.apply_torque_impulse(impulse.torque_impulse / square_meters, true);
+                for (point_impulse, point) in impulse.at_points.drain(..) {
+                    body.apply_impulse_at_point(
+                        to_vector(point_impulse / pixels_per_meter),
+                        Point::from(to_vector(point / pixels_per_meter)),
+                        true,
+                    );
+                }
+                impulse.impulse = Vec2::ZERO;
+                impulse.torque_impulse = 0.0;
+            }
+        }
+
+        for (_, impulse) in world.query_mut::<hecs::Without<&mut ecs::ExternalImpulse, &ecs::RigidBody>>() {
+            *impulse = ecs::ExternalImpulse::default();
+        }
+    }
+
+    fn create_link(
+        &mut self,
+        entity: Entity,
+        transform: &Transform,
+        rigid_body: Option<&ecs::RigidBody>,
+        collider: Option<&ecs::Collider>,
+        is_character: bool,
+    ) -> PhysicsLink {
+        let user_data = entity.to_bits().get() as u128;
+        let isometry = self.isometry(transform.position, transform.rotation);
+        let pixels_per_meter = self.config.pixels_per_meter;
+
+        let body = (rigid_body.is_some() || is_character).then(|| {
+            let mut builder = if is_character {
+                RigidBodyBuilder::kinematic_position_based()
+            } else {
+                RigidBodyBuilder::dynamic()
+            }
+            .position(isometry)
+            .user_data(user_data);
+            if let Some(rigid_body) = rigid_body {
+                builder = builder
+                    .linvel(to_vector(rigid_body.velocity / pixels_per_meter))
+                    .angvel(rigid_body.angular_velocity);
+                if collider.is_none() {
+                    builder = builder.additional_mass(rigid_body.mass);
+                }
+            }
+            let handle = self.rigid_body_set.insert(builder);
+            if let Some(rigid_body) = rigid_body
+                && !is_character
+            {
+                apply_body_settings(&mut self.rigid_body_set[handle], rigid_body, self.config.ccd_enabled);
+            }
+            handle
+        });
+
+        let collider_handle = collider.and_then(|collider| self.insert_collider(entity, transform, collider, body, rigid_body));
+
+        PhysicsLink {
+            body,
+            collider: collider_handle,
+            has_collider: collider.is_some(),
+            is_character,
+            synced_position: transform.position,
+            synced_rotation: transform.rotation,
+            synced_velocity: rigid_body.map_or(Vec2::ZERO, |rigid_body| rigid_body.velocity),
+            synced_angular_velocity: rigid_body.map_or(0.0, |rigid_body| rigid_body.angular_velocity),
+            synced_scale: transform.scale,
+            synced_shape: collider.map(|collider| collider.shape.clone()),
+            synced_trigger: collider.is_some_and(|collider| collider.is_trigger),
+        }
+    }
+
+    /// Builds the rapier collider for `collider`, attached to `body` if there is one.
+    /// Returns `None` if the shape is invalid.
+    fn insert_collider(
+        &mut self,
+        entity: Entity,
+        transform: &Transform,
+        collider: &ecs::Collider,
+        body: Option<RigidBodyHandle>,
+        rigid_body: Option<&ecs::RigidBody>,
+    ) -> Option<ColliderHandle> {
+        let shape = match collider.shape.to_shared_shape(transform.scale / self.config.pixels_per_meter) {
+            Ok(shape) => shape,
+            Err(e) => {
+                log::warn!("Skipping collider for entity {:?}: {}", entity, e);
+                return None;
+            }
+        };
+
+        let builder = ColliderBuilder::new(shape)
+            .sensor(collider.is_trigger)
+            .collision_groups(collider.collision_filter.to_groups())
+            .active_events(ActiveEvents::COLLISION_EVENTS)
+            .active_hooks(ActiveHooks::FILTER_CONTACT_PAIRS | ActiveHooks::FILTER_INTERSECTION_PAIR)
+            .user_data(entity.to_bits().get() as u128);
+        let handle = match body {
+            Some(body) => self.collider_set.insert_with_parent(builder, body, &mut self.rigid_body_set),
+            None => self
+                .collider_set
+                .insert(builder.position(self.isometry(transform.position, transform.rotation))),
+        };
+        if apply_material(&mut self.collider_set[handle], &collider.material, rigid_body)
+            && let Some(body) = body
+        {
+            self.rigid_body_set[body].recompute_mass_properties_from_colliders(&self.collider_set);
+        }
+        self.collider_owners.insert(handle, ColliderOwner {
+            entity,
+            is_trigger: collider.is_trigger,
+        });
+        Some(handle)
+    }
+
+    /// Replaces the rapier collider of a linked entity after its shape, trigger flag
+    /// or scale changed.
+    fn rebuild_collider(
+        &mut self,
+        entity: Entity,
+        transform: &Transform,
+        rigid_body: Option<&ecs::RigidBody>,
+        collider: &ecs::Collider,
+    ) {
+        let Some(mut link) = self.links.remove(&entity) else {
+            return;
+        };
+        if let Some(handle) = link.collider.take() {
+            self.collider_set.remove(handle, &mut self.island_manager, &mut self.rigid_body_set, true);
+            self.removed_colliders.push(handle);
+        }
+        link.collider = self.insert_collider(entity, transform, collider, link.body, rigid_body);
+        if link.body.is_none() {
+            link.synced_position = transform.position;
+            link.synced_rotation = transform.rotation;
+        }
+        link.synced_scale = transform.scale;
+        link.synced_shape = Some(collider.shape.clone());
+        link.synced_trigger = collider.is_trigger;
+        self.links.insert(entity, link);
+    }
+
+    /// Removes rapier objects whose entity was despawned or lost its physics components.
+    fn remove_stale_links(&mut self, world: &World) {
+        let stale: Vec<Entity> = self.links
+            .iter()
+            .filter(|(entity, link)| {
+                let Ok(entity_ref) = world.entity(**entity) else {
+                    return true;
+                };
+                let is_character = entity_ref.has::<ecs::CharacterController>();
+                link.body.is_some() != (entity_ref.has::<ecs::RigidBody>() || is_character)
+                    || link.has_collider != entity_ref.has::<ecs::Collider>()
+                    || link.is_character != is_character
+            })
+            .map(|(entity, _)| *entity)
+            .collect();
+
+        for entity in stale {
+            let Some(link) = self.links.remove(&entity) else {
+                continue;
+            };
+            if let Some(collider) = link.collider {
+                self.collider_set.remove(collider, &mut self.island_manager, &mut self.rigid_body_set, true);
+                self.removed_colliders.push(collider);
+            }
+            if let Some(body) = link.body {
+                self.rigid_body_set.remove(
+                    body,
+                    &mut self.island_manager,
+                    &mut self.collider_set,
+                    &mut self.joint_set,
+                    &mut self.multibody_joint_set,
+                    true,
+                );
+            }
+        }
+    }
+
+    /// Creates, updates and removes rapier joints to match the ECS `Joint` components.
+    /// Runs after bodies are synced so both ends of a new joint exist.
+    fn sync_joints(&mut self, world: &World) {
+        let mut wanted = HashMap::new();
+        let mut missing_bodies = HashSet::new();
+        for (entity, joint) in world.query::<&ecs::Joint>().iter() {
+            let target_body = match joint.target {
+                Some(target) => self.body_handle(target),
+                None => Some(self.world_body()),
+            };
+            match target_body.zip(self.body_handle(entity)) {
+                Some(bodies) => {
+                    wanted.insert(entity, (bodies, *joint));
+                }
+                None => {
+                    if !self.joints_missing_bodies.contains(&entity) {
+                        log::warn!("Joint on entity {:?} needs a body on both ends", entity);
+                    }
+                    missing_bodies.insert(entity);
+                }
+            }
+        }
+        self.joints_missing_bodies = missing_bodies;
+
+        let mut to_remove = Vec::new();
+        for (entity, link) in &self.joints {
+            match wanted.get(entity) {
+                Some((bodies, joint)) if *bodies == link.bodies && joint.multibody == link.joint.multibody => {}
+                _ => to_remove.push(*entity),
+            }
+        }
+        for entity in to_remove {
+            if let Some(link) = self.joints.remove(&entity) {
+                self.remove_joint(link);
+            }
+        }
+
+        // Multibodies have to be built from the root outwards, so parents are inserted
+        // before their children; the entity id breaks ties to keep the order stable.
+        let mut ordered: Vec<_> = wanted.into_iter().collect();
+        let depths: HashMap<Entity, usize> = ordered
+            .iter()
+            .map(|(entity, _)| (*entity, joint_depth(world, *entity)))
+            .collect();
+        ordered.sort_by_key(|(entity, _)| (depths[entity], entity.to_bits()));
+
+        for (entity, (bodies, joint)) in ordered {
+            let data = generic_joint(&joint, self.config.pixels_per_meter);
+            match self.joints.get_mut(&entity) {
+                Some(link) if link.joint == joint => {}
+                Some(link) => {
+                    match link.handle {
+                        JointLinkHandle::Impulse(handle) => {
+                            if let Some(rapier_joint) = self.joint_set.get_mut(handle, true) {
+                                rapier_joint.data = data;
+                            }
+                        }
+                        JointLinkHandle::Multibody(handle) => {
+                            if let Some((multibody, id)) = self.multibody_joint_set.get_mut(handle)
+                                && let Some(multibody_link) = multibody.link_mut(id)
+                            {
+                                multibody_link.joint.data = data;
+                            }
+                        }
+                    }
+                    link.joint = joint;
+                }
+                None => {
+                    let handle = self.insert_joint(entity, bodies, &joint, data);
+                    self.joints.insert(entity, JointLink { handle, bodies, joint });
+                }
+            }
+        }
+    }
+
+    fn insert_joint(
+        &mut self,
+        entity: Entity,
+        (parent, child): (RigidBodyHandle, RigidBodyHandle),
+        joint: &ecs::Joint,
+        data: GenericJoint,
+    ) -> JointLinkHandle {
+        if joint.multibody {
+            // Inserting a joint that would give the child a second parent or close a
+            // loop fails, so check first and fall back to an impulse joint.
+            let parent_link = self.multibody_joint_set.rigid_body_link(parent).copied();
+            let child_link = self.multibody_joint_set.rigid_body_link(child).copied();
+            let valid = match (parent_link, child_link) {
+                (_, Some(child_link)) if child_link.id != 0 => false,
+                (Some(parent_link), Some(child_link)) => parent_link.multibody != child_link.multibody,
+                _ => true,
+            };
+            if valid && let Some(handle) = self.multibody_joint_set.insert(parent, child, data, true) {
+                return JointLinkHandle::Multibody(handle);
+            }
+            log::warn!(
+                "Joint on entity {:?} can't be part of a multibody; using an impulse joint instead",
+                entity
+            );
+        }
+        JointLinkHandle::Impulse(self.joint_set.insert(parent, child, data, true))
+    }
+
+    fn remove_joint(&mut self, link: JointLink) {
+        // Removing a body also removes its joints, and the multibody handle is derived
+        // from the child body, so only remove joints whose bodies are still alive.
+        let (parent, child) = link.bodies;
+        if !self.rigid_body_set.contains(parent) || !self.rigid_body_set.contains(child) {
+            return;
+        }
+        match link.handle {
+            JointLinkHandle::Impulse(handle) => {
+                self.joint_set.remove(handle, true);
+            }
+            JointLinkHandle::Multibody(handle) => self.multibody_joint_set.remove(handle, true),
+        }
+    }
+
+    fn world_body(&mut self) -> RigidBodyHandle {
+        *self
+            .world_body
+            .get_or_insert_with(|| self.rigid_body_set.insert(RigidBodyBuilder::fixed()))
+    }
+
+    /// Sweeps every character's collider along its desired movement and schedules the
+    /// resulting position for its kinematic body, updating the reported contact state.
+    fn move_characters(&mut self, world: &mut World, dt: f32) {
+        if !self.links.values().any(|link| link.is_character) {
+            return;
+        }
+        // Colliders created by this step's sync aren't in the query pipeline yet.
+        self.query_pipeline.update(&self.collider_set);
+
+        for (entity, controller) in world.query_mut::<&mut ecs::CharacterController>() {
+            let Some((body_handle, collider_handle)) = self
+                .links
+                .get(&entity)
+                .and_then(|link| link.body.zip(link.collider))
+            else {
+                continue;
+            };
+            let (Some(body), Some(collider)) = (
+                self.rigid_body_set.get(body_handle),
+                self.collider_set.get(collider_handle),
+            ) else {
+                continue;
+            };
+
+            let up = controller.up.normalize_or(Vec2::Y);
+            let meters = |pixels: f32| CharacterLength::Absolute(pixels / self.config.pixels_per_meter);
+            let character_controller = KinematicCharacterController {
+                up: UnitVector::new_normalize(to_vector(up)),
+                offset: meters(controller.offset),
+                autostep: controller.max_step_height.map(|max_height| CharacterAutostep {
+                    max_height: meters(max_height),
+                    min_width: meters(controller.min_step_width),
+                    include_dynamic_bodies: false,
+                }),
+                max_slope_climb_angle: controller.max_slope_climb_angle,
+                min_slope_slide_angle: controller.min_slope_slide_angle,
+                snap_to_ground: controller.snap_to_ground.map(meters),
+                ..KinematicCharacterController::default()
+            };
+
+            // One-way platforms only block a character that is not moving up and
+            // is already above them.
+            let moving_up = controller.velocity.dot(up) > 0.0;
+            let character_bottom = extent_along(&collider.compute_aabb(), up).0;
+            let tolerance = controller.offset * 2.0 / self.config.pixels_per_meter;
+            let blocks_character = |handle: ColliderHandle, other: &Collider| {
+                !self.one_way_platforms.contains(&handle)
+                    || (!moving_up && character_bottom >= extent_along(&other.compute_aabb(), up).1 - tolerance)
+            };
+            let pair_filter = self.pair_filter.as_deref();
+            let passes_filters = |handle: ColliderHandle, other: &Collider| {
+                blocks_character(handle, other)
+                    && pair_filter.is_none_or(|pair_filter| {
+                        entity_from_user_data(other.user_data).is_none_or(|other| pair_filter(entity, other))
+                    })
+            };
+            let mut filter = QueryFilter::new().exclude_rigid_body(body_handle).exclude_sensors();
+            filter.groups = Some(collider.collision_groups());
+            filter.predicate = Some(&passes_filters);
+
+            let ground_threshold = controller.max_slope_climb_angle.cos();
+            let (mut touching_ceiling, mut touching_wall) = (false, false);
+            let movement = character_controller.move_shape(
+                dt,
+                &self.rigid_body_set,
+                &self.collider_set,
+                &self.query_pipeline,
+                collider.shape(),
+                collider.position(),
+                self.to_meters(controller.velocity * dt),
+                filter,
+                |collision| {
+                    let alignment = Vec2::new(collision.hit.normal1.x, collision.hit.normal1.y).dot(up);
+                    if alignment <= -ground_threshold {
+                        touching_ceiling = true;
+                    } else if alignment < ground_threshold {
+                        touching_wall = true;
+                    }
+                },
+            );
+
+            controller.grounded = movement.grounded;
+            controller.sliding_down_slope = movement.is_sliding_down_slope;
+            controller.touching_ceiling = touching_ceiling;
+            controller.touching_wall = touching_wall;
+
+            let next_translation = body.translation() + movement.translation;
+            if let Some(body) = self.rigid_body_set.get_mut(body_handle) {
+                body.set_next_kinematic_translation(next_translation);
+            }
+        }
+    }
+
+    fn sync_from_physics(&mut self, world: &mut World) {
+        let pixels_per_meter = self.config.pixels_per_meter;
+        // Parents are written back before their children, so each child is made
+        // relative to where its parent ended up after the step, not where it started.
+        let mut entities: Vec<(usize, Entity)> = self
+            .links
+            .iter()
+            .filter(|(_, link)| link.body.is_some())
+            .map(|(entity, _)| (ancestor_count(world, *entity), *entity))
+            .collect();
+        entities.sort_unstable_by_key(|(depth, entity)| (*depth, entity.to_bits()));
+
+        for (_, entity) in entities {
+            let Some(link) = self.links.get_mut(&entity) else {
+                continue;
+            };
+            let Some(body) = link.body.and_then(|handle| self.rigid_body_set.get(handle)) else {
+                continue;
+            };
+            let parent_global = world
+                .get::<&ecs::Parent>(entity)
+                .ok()
+                .and_then(|parent| ecs::compute_global_transform(world, parent.0));
+            let Ok((transform, rigid_body)) =
+                world.query_one_mut::<(&mut Transform, Option<&mut ecs::RigidBody>)>(entity)
+            else {
+                continue;
+            };
+
+            let translation = body.translation();
+            let position = Vec2::new(translation.x, translation.y) * pixels_per_meter;
+            let rotation = body.rotation().angle();
+            match parent_global {
+                // Stored relative to the parent; remember the pose propagation will
+                // compute from it so an unmoved body isn't teleported next step.
+                Some(parent_global) => {
+                    transform.position = parent_global.0.inverse().transform_point2(position);
+                    transform.rotation = rotation - parent_global.rotation();
+                    let synced = parent_global.mul_transform(transform).to_transform();
+                    link.synced_position = synced.position;
+                    link.synced_rotation = synced.rotation;
+                }
+                None => {
+                    transform.position = position;
+                    transform.rotation = rotation;
+                    link.synced_position = transform.position;
+                    link.synced_rotation = transform.rotation;
+                }
+            }
+
+            if let Some(rigid_body) = rigid_body {
+                rigid_body.velocity = Vec2::new(body.linvel().x, body.linvel().y) * pixels_per_meter;
+                rigid_body.angular_velocity = body.angvel();
+                link.synced_velocity = rigid_body.velocity;
+                link.synced_angular_velocity = rigid_body.angular_velocity;
+            }
+        }
+    }
+}
+
+/// Number of `Parent` links above `entity`, capped at the size of the world in case
+/// of a cycle.
+fn ancestor_count(world: &World, entity: Entity) -> usize {
+    let mut count = 0;
+    let mut next = world.get::<&ecs::Parent>(entity).ok().map(|parent| parent.0);
+    while let Some(ancestor) = next
+        && count <= world.len() as usize
+    {
+        count += 1;
+        next = world.get::<&ecs::Parent>(ancestor).ok().map(|parent| parent.0);
+    }
+    count
+}
+
+/// Copies the settings of a `RigidBody` onto its rapier body, touching only what
+/// changed so untouched bodies aren't woken up.
+fn apply_body_settings(body: &mut RigidBody, rigid_body: &ecs::RigidBody, ccd_enabled: bool) {
+    let body_type = match rigid_body.body_type {
+        ecs::BodyType::Dynamic => RigidBodyType::Dynamic,
+        ecs::BodyType::Kinematic => RigidBodyType::KinematicVelocityBased,
+        ecs::BodyType::Static => RigidBodyType::Fixed,
+    };
+    if body.body_type() != body_type {
+        body.set_body_type(body_type, true);
+    }
+    if body.is_ccd_enabled() != (ccd_enabled && body.is_dynamic()) {
+        body.enable_ccd(ccd_enabled && body.is_dynamic());
+    }
+    if body.linear_damping() != rigid_body.linear_damping {
+        body.set_linear_damping(rigid_body.linear_damping);
+    }
+    if body.angular_damping() != rigid_body.angular_damping {
+        body.set_angular_damping(rigid_body.angular_damping);
+    }
+    if body.gravity_scale() != rigid_body.gravity_scale {
+        body.set_gravity_scale(rigid_body.gravity_scale, true);
+    }
+    if body.is_rotation_locked() != rigid_body.lock_rotation {
+        body.lock_rotations(rigid_body.lock_rotation, true);
+    }
+}
+
+/// Copies a `PhysicsMaterial` onto a rapier collider. Without a density the collider
+/// carries the whole `RigidBody::mass`. Returns whether the collider's mass changed, in
+/// which case its body's mass properties are stale until recomputed.
+fn apply_material(collider: &mut Collider, material: &ecs::PhysicsMaterial, rigid_body: Option<&ecs::RigidBody>) -> bool {
+    let combine_rule = |rule: ecs::CombineRule| match rule {
+        ecs::CombineRule::Average => CoefficientCombineRule::Average,
+        ecs::CombineRule::Min => CoefficientCombineRule::Min,
+        ecs::CombineRule::Multiply => CoefficientCombineRule::Multiply,
+        ecs::CombineRule::Max => CoefficientCombineRule::Max,
+    };
+    if collider.friction() != material.friction {
+        collider.set_friction(material.friction);
+    }
+    if collider.restitution() != material.restitution {
+        collider.set_restitution(material.restitution);
+    }
+    if collider.friction_combine_rule() != combine_rule(material.friction_combine) {
+        collider.set_friction_combine_rule(combine_rule(material.friction_combine));
+    }
+    if collider.restitution_combine_rule() != combine_rule(material.restitution_combine) {
+        collider.set_restitution_combine_rule(combine_rule(material.restitution_combine));
+    }
+    match (material.density, rigid_body) {
+        (Some(density), _) if collider.density() != density => collider.set_density(density),
+        (None, Some(rigid_body)) if collider.mass() != rigid_body.mass => collider.set_mass(rigid_body.mass),
+        _ => return false,
+    }
+    true
+}
+
+fn to_vector(v: Vec2) -> Vector<Real> {
+    vector![v.x, v.y]
+}
+
+/// Number of `Joint`s between `entity` and the root of its chain.
+fn joint_depth(world: &World, entity: Entity) -> usize {
+    let mut depth = 0;
+    let mut current = entity;
+    while let Ok(joint) = world.get::<&ecs::Joint>(current)
+        && let Some(target) = joint.target
+    {
+        depth += 1;
+        current = target;
+        // A chain longer than the number of entities has to contain a loop.
+        if depth > world.len() as usize {
+            break;
+        }
+    }
+    depth
+}
+
+/// Builds the rapier joint for a `Joint`. The target is rapier's first body and the
+/// entity the second, so limits and motors act on the entity relative to its target.
+fn generic_joint(joint: &ecs::Joint, pixels_per_meter: f32) -> GenericJoint {
+    let to_meters = |pixels: Vec2| Point::from(to_vector(pixels / pixels_per_meter));
+    let anchor1 = to_meters(joint.target_anchor);
+    let anchor2 = to_meters(joint.anchor);
+
+    let (mut data, free_axis): (GenericJoint, Option<JointAxis>) = match joint.kind {
+        ecs::JointKind::Revolute => (
+            RevoluteJointBuilder::new().local_anchor1(anchor1).local_anchor2(anchor2).into(),
+            Some(JointAxis::AngX),
+        ),
+        ecs::JointKind::Prismatic { axis } => (
+            PrismaticJointBuilder::new(UnitVector::new_normalize(to_vector(axis.normalize_or(Vec2::X))))
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            Some(JointAxis::LinX),
+        ),
+        ecs::JointKind::Fixed => (
+            FixedJointBuilder::new().local_anchor1(anchor1).local_anchor2(anchor2).into(),
+            None,
+        ),
+        ecs::JointKind::Rope { max_length } => (
+            RopeJointBuilder::new(max_length / pixels_per_meter).local_anchor1(anchor1).local_anchor2(anchor2).into(),
+            Some(JointAxis::LinX),
+        ),
+        ecs::JointKind::Spring { rest_length, stiffness, damping } => (
+            SpringJointBuilder::new(rest_length / pixels_per_meter, stiffness, damping)
+                .local_anchor1(anchor1)
+                .local_anchor2(anchor2)
+                .into(),
+            None,
+        ),
+    };
+
+    if let Some(axis) = free_axis {
+        // Linear axes are given in pixels, angular ones in radians.
+        let unit = if axis == JointAxis::AngX { 1.0 } else { 1.0 / pixels_per_meter };
+        let is_rope = matches!(joint.kind, ecs::JointKind::Rope { .. });
+        if let Some([min, max]) = joint.limits
+            && !is_rope
+        {
+            data.set_limits(axis, [min * unit, max * unit]);
+        }
+        if let Some(motor) = joint.motor {
+            data.set_motor(
+                axis,
+                motor.target_position * unit,
+                motor.target_velocity * unit,
+                motor.stiffness,
+                motor.damping,
+            );
+            data.set_motor_max_force(axis, motor.max_force);
+        }
+    }
+    data.set_contacts_enabled(joint.collide_connected);
+    data
+}
+
+/// Smallest and largest projection of the corners of `aabb` onto `axis`.
+fn extent_along(aabb: &Aabb, axis: Vec2) -> (f32, f32) {
+    let center = Vec2::new(aabb.center().x, aabb.center().y);
+    let half_extents = Vec2::new(aabb.half_extents().x, aabb.half_extents().y);
+    let center_projection = center.dot(axis);
+    let radius = half_extents.dot(axis.abs());
+    (center_projection - radius, center_projection + radius)
+}
+
+fn entity_from_user_data(user_data: u128) -> Option<Entity> {
+    Entity::from_bits(user_data as u64)
+}
+
+impl Default for PhysicsSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Built-in system: steps the `PhysicsSystem` resource by its configured timestep,
+/// which `Engine` also uses as the fixed step of `Time`, sends the step's
+/// `PhysicsEvent`s as events, then propagates transforms so children follow the
+/// bodies they're attached to. Does nothing without it.
+pub fn step_physics(world: &mut World, resources: &ecs::Resources) {
+    let Some(mut physics) = resources.get_mut::<PhysicsSystem>() else {
+        return;
+    };
+    let already_sent = physics.events().len();
+    let timestep = physics.config().timestep;
+    physics.update(world, timestep);
+    if let Some(mut events) = resources.get_mut::<ecs::Events<PhysicsEvent>>() {
+        events.send_batch(physics.events()[already_sent..].iter().copied());
+    }
+    ecs::propagate_global_transforms(world);
+}
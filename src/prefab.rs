@@ -0,0 +1,229 @@
+use crate::ecs;
+use crate::scene::{ComponentRegistry, SceneFormat};
+use hecs::{Entity, World};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Per-instance changes to a prefab's components, keyed by registered component name.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Overrides {
+    /// Components replaced outright, or added if the prefab lacks them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, Value>,
+    /// Fields merged into the prefab's components, e.g. `{"Transform": {"position": [10.0, 0.0]}}`.
+    /// Nested objects are merged too; anything else replaces the prefab's value.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub patches: BTreeMap<String, Value>,
+}
+
+impl Overrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the prefab's `name` component with `component`.
+    pub fn with<T: Serialize>(mut self, name: &str, component: &T) -> Self {
+        if let Some(value) = to_value(name, component) {
+            self.components.insert(name.to_string(), value);
+        }
+        self
+    }
+
+    /// Merges `fields` into the prefab's `name` component.
+    pub fn patch(mut self, name: &str, fields: Value) -> Self {
+        match self.patches.get_mut(name) {
+            Some(existing) => merge(existing, fields),
+            None => {
+                self.patches.insert(name.to_string(), fields);
+            }
+        }
+        self
+    }
+
+    fn apply(&self, components: &mut BTreeMap<String, Value>) {
+        for (name, value) in &self.components {
+            components.insert(name.clone(), value.clone());
+        }
+        for (name, fields) in &self.patches {
+            merge(components.entry(name.clone()).or_insert(Value::Null), fields.clone());
+        }
+    }
+}
+
+/// A prefab spawned as a child of another, with its own overrides.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PrefabChild {
+    pub prefab: String,
+    #[serde(default)]
+    pub overrides: Overrides,
+}
+
+/// Named template of components, keyed by registered component name like scene
+/// entities. A prefab can start from a `base` prefab, whose components it replaces
+/// one by one, and spawns its `children` as `Parent`-linked child entities.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default)]
+    pub components: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PrefabChild>,
+}
+
+impl Prefab {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A prefab that starts with every component of the prefab called `base`.
+    pub fn extending(base: &str) -> Self {
+        Self { base: Some(base.to_string()), ..Self::default() }
+    }
+
+    /// Adds `component` under `name`, replacing the base prefab's if it has one.
+    pub fn with<T: Serialize>(mut self, name: &str, component: &T) -> Self {
+        if let Some(value) = to_value(name, component) {
+            self.components.insert(name.to_string(), value);
+        }
+        self
+    }
+
+    /// Spawns the prefab called `prefab` as a child of every instance of this one.
+    pub fn child(mut self, prefab: &str, overrides: Overrides) -> Self {
+        self.children.push(PrefabChild { prefab: prefab.to_string(), overrides });
+        self
+    }
+
+    /// Reads a prefab from a `.ron` or `.json` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let format = SceneFormat::from_path(path)?;
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read prefab {}: {}", path.display(), e))?;
+        let prefab = match format {
+            SceneFormat::Ron => ron::from_str(&text).map_err(|e| e.to_string()),
+            SceneFormat::Json => serde_json::from_str(&text).map_err(|e| e.to_string()),
+        };
+        prefab.map_err(|e| format!("Failed to parse prefab {}: {}", path.display(), e))
+    }
+}
+
+fn to_value<T: Serialize>(name: &str, component: &T) -> Option<Value> {
+    serde_json::to_value(component)
+        .map_err(|e| log::warn!("Failed to serialize {} for a prefab: {}", name, e))
+        .ok()
+}
+
+fn merge(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                merge(target.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
+
+/// Every registered prefab by name. Stored as a resource on `EcsWorld`.
+#[derive(Default)]
+pub struct Prefabs {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl Prefabs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `prefab` under `name`, returning the prefab it replaced.
+    pub fn insert(&mut self, name: &str, prefab: Prefab) -> Option<Prefab> {
+        self.prefabs.insert(name.to_string(), prefab)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Prefab> {
+        self.prefabs.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Components of the prefab called `name`, with those of its bases filled in.
+    pub fn resolve(&self, name: &str) -> Result<BTreeMap<String, Value>, String> {
+        let mut chain = Vec::new();
+        let mut next = Some(name);
+        while let Some(name) = next {
+            if chain.iter().any(|(chained, _)| *chained == name) {
+                return Err(format!("Prefab '{}' is its own base", name));
+            }
+            let prefab = self.get(name).ok_or_else(|| format!("Unknown prefab '{}'", name))?;
+            chain.push((name, prefab));
+            next = prefab.base.as_deref();
+        }
+
+        let mut components = BTreeMap::new();
+        for (_, prefab) in chain.into_iter().rev() {
+            components.extend(prefab.components.iter().map(|(name, value)| (name.clone(), value.clone())));
+        }
+        Ok(components)
+    }
+
+    /// Spawns the prefab called `name` and its children. If any of them fails to spawn,
+    /// nothing is left behind.
+    pub fn spawn(&self, world: &mut World, registry: &ComponentRegistry, name: &str, overrides: &Overrides) -> Result<Entity, String> {
+        let root = world.spawn(());
+        if let Err(error) = self.fill(world, registry, root, name, overrides, &mut Vec::new()) {
+            let _ = ecs::despawn_recursive(world, root);
+            return Err(error);
+        }
+        Ok(root)
+    }
+
+    /// Gives `entity` the prefab's components and spawns its children. `path` holds the
+    /// prefabs being spawned above this one, to catch prefabs that contain themselves.
+    fn fill<'a>(
+        &'a self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+        entity: Entity,
+        name: &'a str,
+        overrides: &Overrides,
+        path: &mut Vec<&'a str>,
+    ) -> Result<(), String> {
+        if path.contains(&name) {
+            return Err(format!("Prefab '{}' contains itself", name));
+        }
+        let mut components = self.resolve(name)?;
+        overrides.apply(&mut components);
+        for (component, value) in components {
+            registry
+                .load_component(world, entity, &component, value)
+                .map_err(|e| format!("Failed to spawn prefab '{}': {}", name, e))?;
+        }
+
+        path.push(name);
+        for child in self.children_of(name) {
+            let child_entity = world.spawn(());
+            ecs::set_parent(world, child_entity, entity)?;
+            self.fill(world, registry, child_entity, &child.prefab, &child.overrides, path)?;
+        }
+        path.pop();
+        Ok(())
+    }
+
+    /// Children of the prefab called `name`, bases' first. Only called once `resolve`
+    /// has checked the base chain.
+    fn children_of(&self, name: &str) -> Vec<&PrefabChild> {
+        let mut children = Vec::new();
+        let mut next = self.get(name);
+        while let Some(prefab) = next {
+            children.splice(0..0, prefab.children.iter());
+            next = prefab.base.as_deref().and_then(|base| self.get(base));
+        }
+        children
+    }
+}
@@ -1,31 +1,159 @@
-use crate::ecs::{EcsWorld};
+use crate::ecs::{Camera2D, EcsWorld, GlobalTransform, Sprite};
+use crate::physics::DebugLine;
+use hecs::Entity;
+use std::ops::Range;
 use std::sync::Arc;
 use wgpu::{Device, Queue, Surface, SurfaceConfiguration};
 use wgpu::util::DeviceExt;
 use winit::window::Window;
 use std::collections::HashMap;
 
+/// Where frames end up: a window's swap chain, or a texture that can be read back.
+enum RenderTarget<'window> {
+    Surface {
+        surface: Surface<'window>,
+        config: SurfaceConfiguration,
+    },
+    Offscreen {
+        texture: wgpu::Texture,
+    },
+}
+
+/// Colour format of the offscreen target. RGBA8 so captured frames need no swizzling.
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
 pub struct Renderer<'window> {
-    surface: Surface<'window>,
+    target: RenderTarget<'window>,
     device: Device,
     queue: Queue,
-    config: SurfaceConfiguration,
     size: (u32, u32),
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
-    bind_group_layout: wgpu::BindGroupLayout,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    camera_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    camera_capacity: usize,
+    camera_stride: usize,
+    cameras: Vec<Camera2D>,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
     texture_bind_groups: HashMap<String, wgpu::BindGroup>,
-    transform_buffers: HashMap<String, wgpu::Buffer>,
+    instance_buffer: wgpu::Buffer,
+    instance_capacity: usize,
+    instances: Vec<SpriteInstance>,
+    batches: Vec<SpriteBatch>,
+    debug_pipeline: wgpu::RenderPipeline,
+    debug_vertex_buffer: wgpu::Buffer,
+    debug_vertex_capacity: usize,
+    debug_vertices: Vec<DebugVertex>,
+    physics_debug: bool,
 }
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-struct TransformUniforms {
-    model_matrix: [f32; 16],
+struct CameraUniforms {
     view_proj_matrix: [f32; 16],
 }
 
+/// Per-sprite data uploaded to the instance buffer, one entry per drawn quad.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteInstance {
+    model_matrix: [f32; 16],
+    uv_rect: [f32; 4],
+    tint: [f32; 4],
+}
+
+impl SpriteInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
+        2 => Float32x4,
+        3 => Float32x4,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+    ];
+
+    fn new(transform: &GlobalTransform, sprite: &Sprite) -> Self {
+        let model_matrix = transform.to_mat4(glam::Vec2::new(sprite.width, sprite.height));
+
+        Self {
+            model_matrix: model_matrix.to_cols_array(),
+            uv_rect: sprite.uv_rect.to_array(),
+            tint: sprite.tint.to_array(),
+        }
+    }
+
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// One end of a physics debug line.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct DebugVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+
+impl DebugVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+        0 => Float32x2,
+        1 => Float32x4,
+    ];
+
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// A contiguous run of instances sharing one texture, drawn with a single call.
+struct SpriteBatch {
+    texture_name: String,
+    instances: Range<u32>,
+}
+
+const INITIAL_INSTANCE_CAPACITY: usize = 1024;
+const INITIAL_CAMERA_CAPACITY: usize = 4;
+const INITIAL_DEBUG_VERTEX_CAPACITY: usize = 4096;
+
+/// Sorts renderables by texture and packs them into `instances`, recording one
+/// batch per texture run. Sorting is stable so spawn order is kept within a texture.
+fn build_sprite_batches(
+    renderables: &mut [(Entity, GlobalTransform, Sprite)],
+    instances: &mut Vec<SpriteInstance>,
+    batches: &mut Vec<SpriteBatch>,
+) {
+    instances.clear();
+    batches.clear();
+
+    renderables.sort_by(|a, b| a.2.texture_name.cmp(&b.2.texture_name));
+
+    for (_entity, transform, sprite) in renderables.iter() {
+        let index = instances.len() as u32;
+        instances.push(SpriteInstance::new(transform, sprite));
+
+        match batches.last_mut() {
+            Some(batch) if batch.texture_name == sprite.texture_name => {
+                batch.instances.end = index + 1;
+            }
+            _ => batches.push(SpriteBatch {
+                texture_name: sprite.texture_name.clone(),
+                instances: index..index + 1,
+            }),
+        }
+    }
+}
+
 impl<'window> Renderer<'window> {
     pub async fn new(window: Arc<Window>) -> Self {
         let size = window.inner_size();
@@ -59,26 +187,89 @@ impl<'window> Renderer<'window> {
         };
         surface.configure(&device, &config);
 
+        Self::with_target(
+            device,
+            queue,
+            RenderTarget::Surface { surface, config },
+            (size.width, size.height),
+        )
+    }
+
+    /// Creates a renderer without a window that draws into an offscreen texture.
+    /// Prefers a hardware adapter and falls back to the software adapter, so it also
+    /// works on CI machines without a GPU.
+    pub async fn new_headless(width: u32, height: u32) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid headless render size: {}x{}", width, height));
+        }
+
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+
+        let adapter = match instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::default(),
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }).await {
+            Ok(adapter) => adapter,
+            Err(_) => instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: true,
+            }).await.map_err(|e| format!("Failed to find a headless adapter: {}", e))?,
+        };
+        log::info!("Headless renderer using adapter: {:?}", adapter.get_info());
+
+        let (device, queue) = adapter.request_device(
+            &wgpu::DeviceDescriptor::default()
+        ).await.map_err(|e| format!("Failed to create device: {}", e))?;
+
+        let texture = create_offscreen_texture(&device, width, height);
+
+        Ok(Self::with_target(
+            device,
+            queue,
+            RenderTarget::Offscreen { texture },
+            (width, height),
+        ))
+    }
+
+    fn with_target(
+        device: Device,
+        queue: Queue,
+        target: RenderTarget<'window>,
+        size: (u32, u32),
+    ) -> Self {
+        let format = match &target {
+            RenderTarget::Surface { config, .. } => config.format,
+            RenderTarget::Offscreen { .. } => OFFSCREEN_FORMAT,
+        };
+
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Sprite Shader"),
             source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(include_str!("../shaders/sprite.wgsl"))),
         });
         
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Bind Group Layout"),
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
             entries: &[
                 wgpu::BindGroupLayoutEntry {
                     binding: 0,
                     visibility: wgpu::ShaderStages::VERTEX,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<CameraUniforms>() as u64),
                     },
                     count: None,
                 },
+            ],
+        });
+
+        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Bind Group Layout"),
+            entries: &[
                 wgpu::BindGroupLayoutEntry {
-                    binding: 1,
+                    binding: 0,
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Texture {
                         sample_type: wgpu::TextureSampleType::Float { filterable: true },
@@ -88,7 +279,7 @@ impl<'window> Renderer<'window> {
                     count: None,
                 },
                 wgpu::BindGroupLayoutEntry {
-                    binding: 2,
+                    binding: 1,
                     visibility: wgpu::ShaderStages::FRAGMENT,
                     ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     count: None,
@@ -98,7 +289,7 @@ impl<'window> Renderer<'window> {
         
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[&bind_group_layout],
+            bind_group_layouts: &[&camera_bind_group_layout, &texture_bind_group_layout],
             push_constant_ranges: &[],
         });
         
@@ -126,6 +317,7 @@ impl<'window> Renderer<'window> {
                             },
                         ],
                     },
+                    SpriteInstance::layout(),
                 ],
             },
             fragment: Some(wgpu::FragmentState {
@@ -133,7 +325,7 @@ impl<'window> Renderer<'window> {
                 entry_point: Some("fs_main"),
                 compilation_options: Default::default(),
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
+                    format,
                     blend: Some(wgpu::BlendState::ALPHA_BLENDING),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
@@ -150,21 +342,21 @@ impl<'window> Renderer<'window> {
                 topology: wgpu::PrimitiveTopology::TriangleList,
                 strip_index_format: None,
                 front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
+                cull_mode: None,
                 unclipped_depth: false,
                 polygon_mode: wgpu::PolygonMode::Fill,
                 conservative: false,
             },
         });
 
-        let vertices = [
+        let vertices: [[f32; 4]; 4] = [
             [-0.5, -0.5, 0.0, 1.0],
             [ 0.5, -0.5, 1.0, 1.0],
             [ 0.5,  0.5, 1.0, 0.0],
             [-0.5,  0.5, 0.0, 0.0],
         ];
         
-        let indices = [
+        let indices: [u16; 6] = [
             0, 1, 2,
             2, 3, 0,
         ];
@@ -181,48 +373,108 @@ impl<'window> Renderer<'window> {
             usage: wgpu::BufferUsages::INDEX,
         });
 
+        let camera_stride = align_to(
+            std::mem::size_of::<CameraUniforms>(),
+            device.limits().min_uniform_buffer_offset_alignment as usize,
+        );
+        let camera_buffer = create_camera_buffer(&device, camera_stride, INITIAL_CAMERA_CAPACITY);
+        let camera_bind_group = create_camera_bind_group(&device, &camera_bind_group_layout, &camera_buffer);
+
+        let instance_buffer = create_instance_buffer(&device, INITIAL_INSTANCE_CAPACITY);
+
+        let debug_pipeline = create_debug_pipeline(&device, &camera_bind_group_layout, format);
+        let debug_vertex_buffer = create_debug_vertex_buffer(&device, INITIAL_DEBUG_VERTEX_CAPACITY);
+
         Self {
-            surface,
+            target,
             device,
             queue,
-            config,
-            size: (size.width, size.height),
+            size,
             pipeline,
             vertex_buffer,
             index_buffer,
-            bind_group_layout,
+            camera_bind_group_layout,
+            camera_buffer,
+            camera_bind_group,
+            camera_capacity: INITIAL_CAMERA_CAPACITY,
+            camera_stride,
+            cameras: Vec::new(),
+            texture_bind_group_layout,
             texture_bind_groups: HashMap::new(),
-            transform_buffers: HashMap::new(),
+            instance_buffer,
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
+            instances: Vec::new(),
+            batches: Vec::new(),
+            debug_pipeline,
+            debug_vertex_buffer,
+            debug_vertex_capacity: INITIAL_DEBUG_VERTEX_CAPACITY,
+            debug_vertices: Vec::new(),
+            physics_debug: false,
         }
     }
     
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0 {
             self.size = (width, height);
-            self.config.width = width;
-            self.config.height = height;
-            self.surface.configure(&self.device, &self.config);
+            match &mut self.target {
+                RenderTarget::Surface { surface, config } => {
+                    config.width = width;
+                    config.height = height;
+                    surface.configure(&self.device, config);
+                }
+                RenderTarget::Offscreen { texture } => {
+                    *texture = create_offscreen_texture(&self.device, width, height);
+                }
+            }
         }
     }
     
-    pub fn render(&mut self, world: &EcsWorld) {
-        let frame = self.surface.get_current_texture()
-            .expect("Failed to acquire next swap chain texture");
-        let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    /// Draws the world, interpolating moving sprites `alpha` of the way between the last
+    /// two simulation steps (see [`crate::time::Time::interpolation_alpha`]).
+    pub fn render(&mut self, world: &EcsWorld, alpha: f32) {
+        let mut renderables = world.get_renderables(alpha);
+        build_sprite_batches(&mut renderables, &mut self.instances, &mut self.batches);
+        log::trace!("Rendering {} sprites in {} batches", self.instances.len(), self.batches.len());
+
+        self.cameras = world.get_active_cameras();
+        if self.cameras.is_empty() {
+            self.cameras.push(self.default_camera());
+        }
+        self.upload_cameras();
+
+        if self.instances.len() > self.instance_capacity {
+            self.instance_capacity = self.instances.len().next_power_of_two();
+            self.instance_buffer = create_instance_buffer(&self.device, self.instance_capacity);
+        }
+        if !self.instances.is_empty() {
+            self.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&self.instances));
+        }
+
+        let draw_debug_lines = self.physics_debug && !self.debug_vertices.is_empty();
+        if draw_debug_lines {
+            if self.debug_vertices.len() > self.debug_vertex_capacity {
+                self.debug_vertex_capacity = self.debug_vertices.len().next_power_of_two();
+                self.debug_vertex_buffer = create_debug_vertex_buffer(&self.device, self.debug_vertex_capacity);
+            }
+            self.queue.write_buffer(&self.debug_vertex_buffer, 0, bytemuck::cast_slice(&self.debug_vertices));
+        }
+
+        let (frame, view) = match &self.target {
+            RenderTarget::Surface { surface, .. } => {
+                let frame = surface.get_current_texture()
+                    .expect("Failed to acquire next swap chain texture");
+                let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+                (Some(frame), view)
+            }
+            RenderTarget::Offscreen { texture } => {
+                (None, texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            }
+        };
         
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("Render Encoder"),
         });
         
-        let renderables = world.get_renderables();
-        
-        println!("Rendering {} sprites", renderables.len());
-        for (i, (_entity, transform, sprite)) in renderables.iter().enumerate() {
-            println!("Sprite {}: {} at pos: ({:.1}, {:.1}), scale: ({:.1}, {:.1})", 
-                     i, sprite.texture_name, transform.position.x, transform.position.y,
-                     transform.scale.x, transform.scale.y);
-        }
-        
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Render Pass"),
@@ -244,59 +496,171 @@ impl<'window> Renderer<'window> {
                 timestamp_writes: None,
             });
             
-            render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            
-            for (_entity, transform, sprite) in renderables {
-                println!("Drawing sprite: {}, pos: ({}, {})", 
-                    sprite.texture_name, transform.position.x, transform.position.y);
-                
-                if let Some(bind_group) = self.texture_bind_groups.get(&sprite.texture_name) {
-                    println!("  Found bind group for: {}", sprite.texture_name);
-                    let model_matrix = glam::Mat4::from_scale_rotation_translation(
-                        glam::Vec3::new(sprite.width * transform.scale.x, 
-                                       sprite.height * transform.scale.y, 1.0),
-                        glam::Quat::from_rotation_z(transform.rotation),
-                        glam::Vec3::new(transform.position.x, transform.position.y, 0.0),
-                    );
-                    
-                    let proj = glam::Mat4::orthographic_lh(
-                        0.0,
-                        self.size.0 as f32, 
-                        0.0, 
-                        self.size.1 as f32,
-                        -1.0, 
-                        1.0,              
-                    );
-                    
-                    let transform_data = TransformUniforms {
-                        model_matrix: model_matrix.to_cols_array(),
-                        view_proj_matrix: proj.to_cols_array(),
-                    };
-
-                    if !self.transform_buffers.contains_key(&sprite.texture_name) {
-                        let transform_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                            label: Some(&format!("Transform Buffer for {}", sprite.texture_name)),
-                            contents: bytemuck::cast_slice(&[transform_data]),
-                            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                        });
-                        self.transform_buffers.insert(sprite.texture_name.clone(), transform_buffer);
+            for (camera_index, camera) in self.cameras.iter().enumerate() {
+                let Some((x, y, width, height)) = camera.viewport_rect(self.size) else {
+                    continue;
+                };
+                render_pass.set_viewport(x, y, width, height, 0.0, 1.0);
+
+                render_pass.set_pipeline(&self.pipeline);
+                render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+                render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+                render_pass.set_bind_group(0, &self.camera_bind_group, &[(camera_index * self.camera_stride) as u32]);
+
+                for batch in &self.batches {
+                    match self.texture_bind_groups.get(&batch.texture_name) {
+                        Some(bind_group) => {
+                            render_pass.set_bind_group(1, bind_group, &[]);
+                            render_pass.draw_indexed(0..6, 0, batch.instances.clone());
+                        }
+                        None => log::warn!("No texture loaded for sprite batch: {}", batch.texture_name),
                     }
+                }
 
-                    let buffer = self.transform_buffers.get(&sprite.texture_name).unwrap();
-                    self.queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[transform_data]));
-                    
-                    render_pass.set_bind_group(0, bind_group, &[]);
-                    render_pass.draw_indexed(0..6, 0, 0..1);
-                } else {
-                    println!("  No bind group found for: {}", sprite.texture_name);
+                if draw_debug_lines {
+                    render_pass.set_pipeline(&self.debug_pipeline);
+                    render_pass.set_vertex_buffer(0, self.debug_vertex_buffer.slice(..));
+                    render_pass.draw(0..self.debug_vertices.len() as u32, 0..1);
                 }
             }
         }
         
         self.queue.submit(std::iter::once(encoder.finish()));
-        frame.present();
+        if let Some(frame) = frame {
+            frame.present();
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Turns the physics debug overlay on or off. Lines are drawn on top of the
+    /// sprites of every camera, in both the windowed and headless paths.
+    pub fn set_physics_debug(&mut self, enabled: bool) {
+        self.physics_debug = enabled;
+    }
+
+    pub fn physics_debug_enabled(&self) -> bool {
+        self.physics_debug
+    }
+
+    /// Replaces the lines drawn by the physics debug overlay, usually with
+    /// [`crate::physics::PhysicsSystem::debug_lines`].
+    pub fn set_debug_lines(&mut self, lines: &[DebugLine]) {
+        self.debug_vertices.clear();
+        for line in lines {
+            let color = line.color.to_array();
+            self.debug_vertices.push(DebugVertex { position: line.start.to_array(), color });
+            self.debug_vertices.push(DebugVertex { position: line.end.to_array(), color });
+        }
+    }
+
+    /// Reads the last rendered frame back from the GPU. Only available for renderers
+    /// created with [`Renderer::new_headless`].
+    pub fn capture_frame(&self) -> Result<image::RgbaImage, String> {
+        let RenderTarget::Offscreen { texture } = &self.target else {
+            return Err("Frame capture requires a headless renderer".to_string());
+        };
+
+        let (width, height) = self.size;
+        let unpadded_bytes_per_row = 4 * width;
+        let padded_bytes_per_row = align_to(
+            unpadded_bytes_per_row as usize,
+            wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize,
+        ) as u32;
+
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Readback Buffer"),
+            size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Frame Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::PollType::Wait)
+            .map_err(|e| format!("Failed to wait for frame readback: {}", e))?;
+        receiver.recv()
+            .map_err(|e| format!("Frame readback was cancelled: {}", e))?
+            .map_err(|e| format!("Failed to map frame readback buffer: {}", e))?;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let data = slice.get_mapped_range();
+            for row in data.chunks(padded_bytes_per_row as usize) {
+                pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        readback_buffer.unmap();
+
+        image::RgbaImage::from_raw(width, height, pixels)
+            .ok_or_else(|| "Captured frame has an unexpected size".to_string())
+    }
+
+    pub fn save_frame_png(&self, path: &str) -> Result<(), String> {
+        self.capture_frame()?
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to save frame to {}: {}", path, e))
+    }
+
+    /// Camera used when the world has no active `Camera2D`: shows the target at 1:1 scale
+    /// with the world origin in the bottom-left corner.
+    fn default_camera(&self) -> Camera2D {
+        Camera2D::new(glam::Vec2::new(self.size.0 as f32 / 2.0, self.size.1 as f32 / 2.0))
+    }
+
+    fn upload_cameras(&mut self) {
+        if self.cameras.len() > self.camera_capacity {
+            self.camera_capacity = self.cameras.len().next_power_of_two();
+            self.camera_buffer = create_camera_buffer(&self.device, self.camera_stride, self.camera_capacity);
+            self.camera_bind_group = create_camera_bind_group(
+                &self.device,
+                &self.camera_bind_group_layout,
+                &self.camera_buffer,
+            );
+        }
+
+        let mut data = vec![0u8; self.cameras.len() * self.camera_stride];
+        for (i, camera) in self.cameras.iter().enumerate() {
+            let uniforms = CameraUniforms {
+                view_proj_matrix: camera.view_projection(self.size).to_cols_array(),
+            };
+            let offset = i * self.camera_stride;
+            data[offset..offset + std::mem::size_of::<CameraUniforms>()]
+                .copy_from_slice(bytemuck::bytes_of(&uniforms));
+        }
+        self.queue.write_buffer(&self.camera_buffer, 0, &data);
     }
 
     pub fn load_texture(&mut self, name: &str, data: &[u8], width: u32, height: u32) {
@@ -344,33 +708,16 @@ impl<'window> Renderer<'window> {
             ..Default::default()
         });
         
-        let default_transform = TransformUniforms {
-            model_matrix: glam::Mat4::IDENTITY.to_cols_array(),
-            view_proj_matrix: glam::Mat4::IDENTITY.to_cols_array(),
-        };
-        
-        let transform_buffer = self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some(&format!("Transform Buffer for {}", name)),
-            contents: bytemuck::cast_slice(&[default_transform]),
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-        });
-        
-        self.transform_buffers.insert(name.to_string(), transform_buffer);
-        
         let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some(&format!("Texture Bind Group {}", name)),
-            layout: &self.bind_group_layout,
+            layout: &self.texture_bind_group_layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
-                    resource: self.transform_buffers.get(name).unwrap().as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
                     resource: wgpu::BindingResource::TextureView(&texture_view),
                 },
                 wgpu::BindGroupEntry {
-                    binding: 2,
+                    binding: 1,
                     resource: wgpu::BindingResource::Sampler(&sampler),
                 },
             ],
@@ -378,4 +725,129 @@ impl<'window> Renderer<'window> {
         
         self.texture_bind_groups.insert(name.to_string(), bind_group);
     }
+}
+
+fn create_instance_buffer(device: &Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Sprite Instance Buffer"),
+        size: (capacity * std::mem::size_of::<SpriteInstance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_debug_vertex_buffer(device: &Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Debug Line Vertex Buffer"),
+        size: (capacity * std::mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Line-list pipeline for the physics debug overlay, sharing the sprite camera uniforms.
+fn create_debug_pipeline(
+    device: &Device,
+    camera_bind_group_layout: &wgpu::BindGroupLayout,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Debug Line Shader"),
+        source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(include_str!("../shaders/debug_lines.wgsl"))),
+    });
+
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Debug Line Pipeline Layout"),
+        bind_group_layouts: &[camera_bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Debug Line Pipeline"),
+        layout: Some(&layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[DebugVertex::layout()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            compilation_options: Default::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::LineList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+    })
+}
+
+fn create_camera_buffer(device: &Device, stride: usize, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Camera Buffer"),
+        size: (stride * capacity) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn create_camera_bind_group(
+    device: &Device,
+    layout: &wgpu::BindGroupLayout,
+    buffer: &wgpu::Buffer,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Camera Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                    buffer,
+                    offset: 0,
+                    size: wgpu::BufferSize::new(std::mem::size_of::<CameraUniforms>() as u64),
+                }),
+            },
+        ],
+    })
+}
+
+fn align_to(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+fn create_offscreen_texture(device: &Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Render Target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: OFFSCREEN_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::ecs::{Events, Resources};
+use hecs::World;
 use std::collections::HashMap;
 use std::path::Path;
 use image::GenericImageView;
@@ -8,14 +10,24 @@ pub struct Texture {
     pub data: Vec<u8>,
 }
 
+/// Sent as an event once an asset has been loaded into the `ResourceManager`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetLoaded {
+    pub name: String,
+    pub path: String,
+}
+
 pub struct ResourceManager {
     textures: HashMap<String, Texture>,
+    /// Loads not yet sent as `AssetLoaded` events.
+    loaded: Vec<AssetLoaded>,
 }
 
 impl ResourceManager {
     pub fn new() -> Self {
         Self {
             textures: HashMap::new(),
+            loaded: Vec::new(),
         }
     }
     
@@ -33,10 +45,31 @@ impl ResourceManager {
         };
         
         self.textures.insert(name.to_string(), texture);
+        self.loaded.push(AssetLoaded {
+            name: name.to_string(),
+            path: path.to_string(),
+        });
         Ok(())
     }
     
     pub fn get_texture(&self, name: &str) -> Option<&Texture> {
         self.textures.get(name)
     }
+}
+
+impl Default for ResourceManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Built-in system: sends an `AssetLoaded` event for every asset the
+/// `ResourceManager` resource loaded since it last ran.
+pub fn send_asset_events(_world: &mut World, resources: &Resources) {
+    let (Some(mut manager), Some(mut events)) =
+        (resources.get_mut::<ResourceManager>(), resources.get_mut::<Events<AssetLoaded>>())
+    else {
+        return;
+    };
+    events.send_batch(manager.loaded.drain(..));
 }
\ No newline at end of file
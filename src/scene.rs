@@ -0,0 +1,214 @@
+use crate::ecs::{self, Camera2D, CharacterController, Collider, ExternalForce, OneWayPlatform, RigidBody, Sprite, Transform};
+use hecs::{Component, Entity, World};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Text formats a `Scene` can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SceneFormat {
+    Ron,
+    Json,
+}
+
+impl SceneFormat {
+    /// Picks the format from a `.ron` or `.json` extension.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => Ok(SceneFormat::Ron),
+            Some("json") => Ok(SceneFormat::Json),
+            _ => Err(format!("Unknown scene format for {}: expected a .ron or .json file", path.display())),
+        }
+    }
+}
+
+struct ComponentFns {
+    save: fn(&World, Entity) -> Option<Result<Value, String>>,
+    load: fn(&mut World, Entity, Value) -> Result<(), String>,
+}
+
+/// Components that scenes save and load, keyed by the name they have in scene files.
+/// Stored as a resource on `EcsWorld`, which registers the engine's own components;
+/// games add theirs with `EcsWorld::register_component`. Components that aren't
+/// registered are left out of saved scenes.
+pub struct ComponentRegistry {
+    components: BTreeMap<String, ComponentFns>,
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register::<Transform>("Transform");
+        registry.register::<Sprite>("Sprite");
+        registry.register::<RigidBody>("RigidBody");
+        registry.register::<Collider>("Collider");
+        registry.register::<ExternalForce>("ExternalForce");
+        registry.register::<CharacterController>("CharacterController");
+        registry.register::<OneWayPlatform>("OneWayPlatform");
+        registry.register::<Camera2D>("Camera2D");
+        registry
+    }
+}
+
+impl ComponentRegistry {
+    /// A registry without the engine's components.
+    pub fn empty() -> Self {
+        Self { components: BTreeMap::new() }
+    }
+
+    /// Registers `T` under `name`, replacing whatever was registered under it before.
+    pub fn register<T: Component + Serialize + DeserializeOwned>(&mut self, name: &str) {
+        self.components.insert(
+            name.to_string(),
+            ComponentFns { save: save_component::<T>, load: load_component::<T> },
+        );
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.components.contains_key(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.components.keys().map(String::as_str)
+    }
+
+    /// Serializes every registered component of `entity`, keyed by name.
+    pub fn save_entity(&self, world: &World, entity: Entity) -> Result<BTreeMap<String, Value>, String> {
+        let mut saved = BTreeMap::new();
+        for (name, fns) in &self.components {
+            if let Some(value) = (fns.save)(world, entity) {
+                let value = value.map_err(|e| format!("Failed to save {} of {:?}: {}", name, entity, e))?;
+                saved.insert(name.clone(), value);
+            }
+        }
+        Ok(saved)
+    }
+
+    /// Deserializes a registered component and adds it to `entity`, replacing any it has.
+    pub fn load_component(&self, world: &mut World, entity: Entity, name: &str, value: Value) -> Result<(), String> {
+        let fns = self
+            .components
+            .get(name)
+            .ok_or_else(|| format!("Unknown component '{}': it was never registered", name))?;
+        (fns.load)(world, entity, value).map_err(|e| format!("Failed to load {} into {:?}: {}", name, entity, e))
+    }
+}
+
+fn save_component<T: Component + Serialize>(world: &World, entity: Entity) -> Option<Result<Value, String>> {
+    let component = world.get::<&T>(entity).ok()?;
+    Some(serde_json::to_value(&*component).map_err(|e| e.to_string()))
+}
+
+fn load_component<T: Component + DeserializeOwned>(world: &mut World, entity: Entity, value: Value) -> Result<(), String> {
+    let component: T = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    world.insert_one(entity, component).map_err(|e| e.to_string())
+}
+
+/// One saved entity. `parent` is the index of its parent in `Scene::entities`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneEntity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+    pub components: BTreeMap<String, Value>,
+}
+
+/// Entities and their registered components as data, independent of any `World`.
+/// Entity handles aren't saved; `Parent` links are stored as indices instead, and
+/// components holding other handles, like `Joint`, can't be registered.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    pub entities: Vec<SceneEntity>,
+}
+
+impl Scene {
+    /// Captures every entity that has at least one registered component, in the order
+    /// they were spawned.
+    pub fn from_world(world: &World, registry: &ComponentRegistry) -> Result<Self, String> {
+        let mut saved = Vec::new();
+        for entity in world.iter().map(|entity_ref| entity_ref.entity()) {
+            let components = registry.save_entity(world, entity)?;
+            if !components.is_empty() {
+                saved.push((entity, components));
+            }
+        }
+        saved.sort_by_key(|(entity, _)| entity.id());
+
+        let index_of: HashMap<Entity, usize> = saved.iter().enumerate().map(|(index, (entity, _))| (*entity, index)).collect();
+        let entities = saved
+            .into_iter()
+            .map(|(entity, components)| SceneEntity {
+                parent: world
+                    .get::<&ecs::Parent>(entity)
+                    .ok()
+                    .and_then(|parent| index_of.get(&parent.0).copied()),
+                components,
+            })
+            .collect();
+        Ok(Self { entities })
+    }
+
+    /// Spawns the scene's entities into `world`, returning them in scene order. If any
+    /// component fails to load, everything spawned so far is despawned again.
+    pub fn spawn_into(&self, world: &mut World, registry: &ComponentRegistry) -> Result<Vec<Entity>, String> {
+        let spawned: Vec<Entity> = self.entities.iter().map(|_| world.spawn(())).collect();
+        let result = self.fill(world, registry, &spawned);
+        if let Err(error) = result {
+            for entity in spawned {
+                let _ = world.despawn(entity);
+            }
+            return Err(error);
+        }
+        Ok(spawned)
+    }
+
+    fn fill(&self, world: &mut World, registry: &ComponentRegistry, spawned: &[Entity]) -> Result<(), String> {
+        for (scene_entity, entity) in self.entities.iter().zip(spawned) {
+            for (name, value) in &scene_entity.components {
+                registry.load_component(world, *entity, name, value.clone())?;
+            }
+        }
+        for (index, scene_entity) in self.entities.iter().enumerate() {
+            if let Some(parent) = scene_entity.parent {
+                let parent = *spawned
+                    .get(parent)
+                    .ok_or_else(|| format!("Scene entity {} has a parent index {} out of range", index, parent))?;
+                ecs::set_parent(world, spawned[index], parent)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_string(&self, format: SceneFormat) -> Result<String, String> {
+        match format {
+            SceneFormat::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string()),
+            SceneFormat::Json => serde_json::to_string_pretty(self).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn from_str(text: &str, format: SceneFormat) -> Result<Self, String> {
+        match format {
+            SceneFormat::Ron => ron::from_str(text).map_err(|e| e.to_string()),
+            SceneFormat::Json => serde_json::from_str(text).map_err(|e| e.to_string()),
+        }
+    }
+
+    /// Writes the scene to a `.ron` or `.json` file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let text = self
+            .to_string(SceneFormat::from_path(path)?)
+            .map_err(|e| format!("Failed to serialize scene {}: {}", path.display(), e))?;
+        fs::write(path, text).map_err(|e| format!("Failed to write scene {}: {}", path.display(), e))
+    }
+
+    /// Reads a scene from a `.ron` or `.json` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let format = SceneFormat::from_path(path)?;
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read scene {}: {}", path.display(), e))?;
+        Self::from_str(&text, format).map_err(|e| format!("Failed to parse scene {}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,219 @@
+use crate::ecs::{self, Resources};
+use hecs::World;
+use std::collections::{BTreeSet, HashMap};
+
+/// Points in the frame where systems run, listed in the order they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    /// Once per frame, before any simulation step.
+    PreUpdate,
+    /// Once per fixed simulation step, before physics. May run several times, or not
+    /// at all, in a frame.
+    FixedUpdate,
+    /// Once per frame, after the frame's simulation steps.
+    Update,
+    /// Once per frame, after `Update`.
+    PostUpdate,
+    /// Once per rendered frame, right before drawing.
+    PreRender,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Stage::PreUpdate,
+        Stage::FixedUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::PreRender,
+    ];
+}
+
+pub type SystemFn = dyn FnMut(&mut World, &Resources);
+pub type RunCondition = dyn Fn(&World, &Resources) -> bool;
+
+/// A named piece of game logic, with the systems it has to run before or after in its
+/// stage and the conditions that have to hold for it to run.
+pub struct System {
+    name: String,
+    run: Box<SystemFn>,
+    before: Vec<String>,
+    after: Vec<String>,
+    run_conditions: Vec<Box<RunCondition>>,
+}
+
+impl System {
+    pub fn new(name: &str, run: impl FnMut(&mut World, &Resources) + 'static) -> Self {
+        Self {
+            name: name.to_string(),
+            run: Box::new(run),
+            before: Vec::new(),
+            after: Vec::new(),
+            run_conditions: Vec::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Runs this system before the system called `name` in the same stage. Names
+    /// that aren't registered in the stage are ignored.
+    pub fn before(mut self, name: &str) -> Self {
+        self.before.push(name.to_string());
+        self
+    }
+
+    /// Runs this system after the system called `name` in the same stage. Names that
+    /// aren't registered in the stage are ignored.
+    pub fn after(mut self, name: &str) -> Self {
+        self.after.push(name.to_string());
+        self
+    }
+
+    /// Skips the system whenever `condition` returns `false`. All conditions have to
+    /// hold for the system to run.
+    pub fn run_if(mut self, condition: impl Fn(&World, &Resources) -> bool + 'static) -> Self {
+        self.run_conditions.push(Box::new(condition));
+        self
+    }
+}
+
+/// Systems of one stage and the order they run in.
+#[derive(Default)]
+struct StageSystems {
+    systems: Vec<System>,
+    order: Vec<usize>,
+}
+
+impl StageSystems {
+    /// Orders systems so every `before`/`after` constraint holds, otherwise keeping
+    /// registration order. Fails with the names of the systems caught in a cycle.
+    fn sorted_order(&self) -> Result<Vec<usize>, String> {
+        let index_of: HashMap<&str, usize> = self
+            .systems
+            .iter()
+            .enumerate()
+            .map(|(index, system)| (system.name.as_str(), index))
+            .collect();
+
+        let mut successors = vec![Vec::new(); self.systems.len()];
+        let mut predecessor_count = vec![0; self.systems.len()];
+        for (index, system) in self.systems.iter().enumerate() {
+            let edges = system
+                .before
+                .iter()
+                .filter_map(|name| index_of.get(name.as_str()).map(|other| (index, *other)))
+                .chain(system.after.iter().filter_map(|name| index_of.get(name.as_str()).map(|other| (*other, index))));
+            for (first, then) in edges {
+                successors[first].push(then);
+                predecessor_count[then] += 1;
+            }
+        }
+
+        let mut ready: BTreeSet<usize> = (0..self.systems.len()).filter(|index| predecessor_count[*index] == 0).collect();
+        let mut order = Vec::with_capacity(self.systems.len());
+        while let Some(index) = ready.pop_first() {
+            order.push(index);
+            for then in &successors[index] {
+                predecessor_count[*then] -= 1;
+                if predecessor_count[*then] == 0 {
+                    ready.insert(*then);
+                }
+            }
+        }
+
+        if order.len() < self.systems.len() {
+            let cycle: Vec<&str> = (0..self.systems.len())
+                .filter(|index| predecessor_count[*index] > 0)
+                .map(|index| self.systems[index].name.as_str())
+                .collect();
+            return Err(cycle.join(", "));
+        }
+        Ok(order)
+    }
+}
+
+/// Every registered system, grouped by stage.
+#[derive(Default)]
+pub struct Schedule {
+    stages: HashMap<Stage, StageSystems>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a system. Fails if a system with the same name is already registered
+    /// in any stage, or if its ordering constraints contradict those already in place.
+    pub fn add_system(&mut self, stage: Stage, system: System) -> Result<(), String> {
+        if let Some(existing) = self.stage_of(&system.name) {
+            return Err(format!("A system named '{}' is already registered in {:?}", system.name, existing));
+        }
+
+        let stage_systems = self.stages.entry(stage).or_default();
+        stage_systems.systems.push(system);
+        match stage_systems.sorted_order() {
+            Ok(order) => {
+                stage_systems.order = order;
+                Ok(())
+            }
+            Err(cycle) => {
+                let system = stage_systems.systems.pop().unwrap();
+                Err(format!(
+                    "Adding system '{}' to {:?} would create an ordering cycle between: {}",
+                    system.name, stage, cycle
+                ))
+            }
+        }
+    }
+
+    /// Unregisters a system, returning whether it existed.
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let Some(stage) = self.stage_of(name) else {
+            return false;
+        };
+        let stage_systems = self.stages.get_mut(&stage).unwrap();
+        stage_systems.systems.retain(|system| system.name != name);
+        // Removing a system can't introduce a cycle.
+        stage_systems.order = stage_systems.sorted_order().unwrap_or_default();
+        true
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.stage_of(name).is_some()
+    }
+
+    pub fn stage_of(&self, name: &str) -> Option<Stage> {
+        Stage::ALL.into_iter().find(|stage| {
+            self.stages
+                .get(stage)
+                .is_some_and(|stage_systems| stage_systems.systems.iter().any(|system| system.name == name))
+        })
+    }
+
+    /// Names of a stage's systems in the order they run.
+    pub fn system_names(&self, stage: Stage) -> Vec<&str> {
+        self.stages.get(&stage).map_or_else(Vec::new, |stage_systems| {
+            stage_systems
+                .order
+                .iter()
+                .map(|index| stage_systems.systems[*index].name.as_str())
+                .collect()
+        })
+    }
+
+    /// Runs a stage's systems in order, skipping those whose run conditions fail, then
+    /// flushes the `Commands` they queued.
+    pub fn run_stage(&mut self, stage: Stage, world: &mut World, resources: &Resources) {
+        if let Some(stage_systems) = self.stages.get_mut(&stage) {
+            for index in &stage_systems.order {
+                let system = &mut stage_systems.systems[*index];
+                if system.run_conditions.iter().all(|condition| condition(world, resources)) {
+                    (system.run)(world, resources);
+                }
+            }
+        }
+        ecs::apply_commands(world, resources);
+    }
+}
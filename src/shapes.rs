@@ -0,0 +1,259 @@
+use crate::ecs::Transform;
+use crate::resources::Texture;
+use glam::Vec2;
+use rapier2d::parry::transformation::convex_hull;
+use rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Number of segments used when a round shape has to be approximated by a polygon
+/// because it is scaled non-uniformly.
+const ROUND_SHAPE_SEGMENTS: usize = 24;
+
+/// Geometry of a `Collider`, in pixels and relative to the entity's `Transform`.
+/// The transform's rotation and scale are applied when the shape is handed to rapier.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColliderShape {
+    Cuboid { half_extents: Vec2 },
+    Ball { radius: f32 },
+    /// A capsule along the local Y axis: two half-circles of `radius` whose centres are
+    /// `half_height` above and below the origin.
+    Capsule { half_height: f32, radius: f32 },
+    /// A convex polygon; the convex hull of `points` is used if they aren't convex.
+    ConvexPolygon { points: Vec<Vec2> },
+    /// An open chain of segments, typically for terrain outlines.
+    Polyline { points: Vec<Vec2> },
+    TriMesh { vertices: Vec<Vec2>, indices: Vec<[u32; 3]> },
+    /// Several shapes, each placed at an offset and rotation relative to the entity.
+    /// Nested compounds are flattened; polylines and triangle meshes can't be parts.
+    Compound(Vec<(Vec2, f32, ColliderShape)>),
+}
+
+impl ColliderShape {
+    pub fn cuboid(width: f32, height: f32) -> Self {
+        ColliderShape::Cuboid { half_extents: Vec2::new(width / 2.0, height / 2.0) }
+    }
+
+    /// Builds a convex hull around the opaque pixels of a texture drawn at `size`
+    /// pixels. Returns `None` if fewer than three distinct corners pass the threshold,
+    /// or if `texture.data` is too short for its size.
+    pub fn from_alpha_hull(texture: &Texture, size: Vec2, alpha_threshold: u8) -> Option<Self> {
+        if texture.width == 0 || texture.height == 0 {
+            return None;
+        }
+        let expected_len = texture.width as usize * texture.height as usize * 4;
+        if texture.data.len() < expected_len {
+            log::warn!(
+                "Texture data is {} bytes, expected {} for {}x{} RGBA",
+                texture.data.len(),
+                expected_len,
+                texture.width,
+                texture.height
+            );
+            return None;
+        }
+
+        let pixel_size = Vec2::new(size.x / texture.width as f32, size.y / texture.height as f32);
+        let to_local = |x: u32, y: u32| {
+            Vec2::new(
+                x as f32 * pixel_size.x - size.x / 2.0,
+                size.y / 2.0 - y as f32 * pixel_size.y,
+            )
+        };
+
+        // Only the outermost opaque pixel on each side of a row can contribute to the
+        // hull, so each row adds at most four corners.
+        let mut points = Vec::new();
+        for y in 0..texture.height {
+            let row_start = y as usize * texture.width as usize * 4;
+            let row = &texture.data[row_start..row_start + texture.width as usize * 4];
+            let is_opaque = |x: &u32| row[(*x * 4 + 3) as usize] > alpha_threshold;
+
+            let (Some(left), Some(right)) = (
+                (0..texture.width).find(is_opaque),
+                (0..texture.width).rev().find(is_opaque),
+            ) else {
+                continue;
+            };
+            points.extend([
+                to_local(left, y),
+                to_local(left, y + 1),
+                to_local(right + 1, y),
+                to_local(right + 1, y + 1),
+            ]);
+        }
+
+        if points.len() < 3 {
+            return None;
+        }
+        let hull = convex_hull(&to_points(&points, Vec2::ONE));
+        (hull.len() >= 3).then(|| ColliderShape::ConvexPolygon {
+            points: hull.iter().map(|p| Vec2::new(p.x, p.y)).collect(),
+        })
+    }
+
+    /// Converts to a rapier shape with `scale` applied. Round shapes under non-uniform
+    /// scale are approximated by polygons.
+    pub fn to_shared_shape(&self, scale: Vec2) -> Result<SharedShape, String> {
+        let uniform_scale = (scale.x.abs() == scale.y.abs()).then_some(scale.x.abs());
+
+        match self {
+            ColliderShape::Cuboid { half_extents } => {
+                let half_extents = *half_extents * scale.abs();
+                Ok(SharedShape::cuboid(half_extents.x, half_extents.y))
+            }
+            ColliderShape::Ball { radius } => match uniform_scale {
+                Some(factor) => Ok(SharedShape::ball(radius * factor)),
+                None => convex_polygon(&self.outline(), scale),
+            },
+            ColliderShape::Capsule { half_height, radius } => match uniform_scale {
+                Some(factor) => Ok(SharedShape::capsule_y(half_height * factor, radius * factor)),
+                None => convex_polygon(&self.outline(), scale),
+            },
+            ColliderShape::ConvexPolygon { points } => convex_polygon(points, scale),
+            ColliderShape::Polyline { points } => {
+                if points.len() < 2 {
+                    return Err("A polyline collider needs at least two points".to_string());
+                }
+                Ok(SharedShape::polyline(to_points(points, scale), None))
+            }
+            ColliderShape::TriMesh { vertices, indices } => {
+                SharedShape::trimesh(to_points(vertices, scale), indices.clone())
+                    .map_err(|e| format!("Invalid triangle mesh collider: {:?}", e))
+            }
+            ColliderShape::Compound(parts) => {
+                let mut flattened = Vec::new();
+                compound_parts(parts, scale, Isometry::identity(), &mut flattened)?;
+                if flattened.is_empty() {
+                    return Err("A compound collider needs at least one part".to_string());
+                }
+                Ok(SharedShape::compound(flattened))
+            }
+        }
+    }
+
+    /// World-space bounding box `(min, max)` of the shape placed at `transform`.
+    pub fn bounding_box(&self, transform: &Transform) -> (Vec2, Vec2) {
+        let (sin, cos) = transform.rotation.sin_cos();
+        let to_world = |point: Vec2| {
+            let scaled = point * transform.scale;
+            transform.position + Vec2::new(scaled.x * cos - scaled.y * sin, scaled.x * sin + scaled.y * cos)
+        };
+
+        let points: Vec<Vec2> = match self {
+            ColliderShape::Compound(parts) => {
+                let mut min = Vec2::splat(f32::INFINITY);
+                let mut max = Vec2::splat(f32::NEG_INFINITY);
+                for (offset, rotation, shape) in parts {
+                    let part_transform = Transform {
+                        position: to_world(*offset),
+                        rotation: transform.rotation + rotation,
+                        scale: transform.scale,
+                    };
+                    let (part_min, part_max) = shape.bounding_box(&part_transform);
+                    min = min.min(part_min);
+                    max = max.max(part_max);
+                }
+                return (min, max);
+            }
+            // Measured exactly: the outline sits inside the curve.
+            ColliderShape::Ball { radius } => return round_bounding_box(transform, 0.0, *radius),
+            ColliderShape::Capsule { half_height, radius } => return round_bounding_box(transform, *half_height, *radius),
+            ColliderShape::TriMesh { vertices, .. } => vertices.iter().copied().map(to_world).collect(),
+            _ => self.outline().into_iter().map(to_world).collect(),
+        };
+
+        points.iter().fold(
+            (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+            |(min, max), point| (min.min(*point), max.max(*point)),
+        )
+    }
+
+    /// Local-space outline points (polygon approximation for round shapes).
+    fn outline(&self) -> Vec<Vec2> {
+        match self {
+            ColliderShape::Cuboid { half_extents } => vec![
+                Vec2::new(-half_extents.x, -half_extents.y),
+                Vec2::new(half_extents.x, -half_extents.y),
+                Vec2::new(half_extents.x, half_extents.y),
+                Vec2::new(-half_extents.x, half_extents.y),
+            ],
+            ColliderShape::Ball { radius } => arc(Vec2::ZERO, *radius, 0.0, std::f32::consts::TAU, ROUND_SHAPE_SEGMENTS),
+            ColliderShape::Capsule { half_height, radius } => {
+                let half_segments = ROUND_SHAPE_SEGMENTS / 2;
+                let mut points = arc(Vec2::new(0.0, *half_height), *radius, 0.0, std::f32::consts::PI, half_segments);
+                points.extend(arc(
+                    Vec2::new(0.0, -half_height),
+                    *radius,
+                    std::f32::consts::PI,
+                    std::f32::consts::PI,
+                    half_segments,
+                ));
+                points
+            }
+            ColliderShape::ConvexPolygon { points } | ColliderShape::Polyline { points } => points.clone(),
+            ColliderShape::TriMesh { vertices, .. } => vertices.clone(),
+            ColliderShape::Compound(parts) => parts
+                .iter()
+                .flat_map(|(offset, rotation, shape)| {
+                    let rotation = Vec2::from_angle(*rotation);
+                    shape.outline().into_iter().map(move |p| rotation.rotate(p) + *offset)
+                })
+                .collect(),
+        }
+    }
+}
+
+/// World-space bounding box of a capsule (a ball when `half_height` is zero) placed at
+/// `transform`. Scaled and rotated, each cap is an ellipse around its segment end.
+fn round_bounding_box(transform: &Transform, half_height: f32, radius: f32) -> (Vec2, Vec2) {
+    let (sin, cos) = transform.rotation.sin_cos();
+    let scale = transform.scale;
+    let extents = radius * Vec2::new((scale.x * cos).hypot(scale.y * sin), (scale.x * sin).hypot(scale.y * cos));
+    let end = half_height * scale.y * Vec2::new(-sin, cos);
+    let (top, bottom) = (transform.position + end, transform.position - end);
+    (top.min(bottom) - extents, top.max(bottom) + extents)
+}
+
+/// `segments + 1` points on a circular arc starting at `start` radians and sweeping `sweep`.
+fn arc(center: Vec2, radius: f32, start: f32, sweep: f32, segments: usize) -> Vec<Vec2> {
+    (0..=segments)
+        .map(|i| {
+            let angle = start + sweep * i as f32 / segments as f32;
+            center + Vec2::new(angle.cos(), angle.sin()) * radius
+        })
+        .collect()
+}
+
+/// Appends the rapier shapes of a compound's `parts`, placed relative to `placement`,
+/// to `out`. Nested compounds are flattened since rapier doesn't allow them.
+fn compound_parts(
+    parts: &[(Vec2, f32, ColliderShape)],
+    scale: Vec2,
+    placement: Isometry<Real>,
+    out: &mut Vec<(Isometry<Real>, SharedShape)>,
+) -> Result<(), String> {
+    for (offset, rotation, shape) in parts {
+        let offset = *offset * scale;
+        let part_placement = placement * Isometry::new(vector![offset.x, offset.y], *rotation);
+        match shape {
+            ColliderShape::Compound(nested) => compound_parts(nested, scale, part_placement, out)?,
+            ColliderShape::Polyline { .. } | ColliderShape::TriMesh { .. } => {
+                return Err("A compound collider can't contain a polyline or triangle mesh part".to_string());
+            }
+            shape => out.push((part_placement, shape.to_shared_shape(scale)?)),
+        }
+    }
+    Ok(())
+}
+
+fn to_points(points: &[Vec2], scale: Vec2) -> Vec<Point<Real>> {
+    points.iter().map(|p| point![p.x * scale.x, p.y * scale.y]).collect()
+}
+
+fn convex_polygon(points: &[Vec2], scale: Vec2) -> Result<SharedShape, String> {
+    let error = || "Convex polygon collider needs at least three non-collinear points".to_string();
+    if points.len() < 3 {
+        return Err(error());
+    }
+    SharedShape::convex_hull(&to_points(points, scale)).ok_or_else(error)
+}
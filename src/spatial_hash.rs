@@ -0,0 +1,251 @@
+use glam::Vec2;
+use hecs::Entity;
+use std::collections::{HashMap, HashSet};
+
+/// Cell size in pixels used by `EcsWorld` for its collider index.
+pub const DEFAULT_CELL_SIZE: f32 = 64.0;
+
+/// Boxes spanning more cells than this are kept out of the grid and checked against
+/// every query instead, so huge colliders don't fill millions of cells.
+pub const MAX_CELLS_PER_BOX: u64 = 1024;
+
+type Cell = (i32, i32);
+
+/// Uniform grid over axis-aligned bounding boxes, for overlap checks between entities
+/// that aren't simulated by rapier. Each entity is stored in every cell its box
+/// touches, so queries only look at nearby entities; boxes too big for that are kept
+/// in a separate list that every query checks. Boxes are in pixels; touching edges
+/// don't count as overlapping.
+#[derive(Clone, Debug)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<Entity>>,
+    bounds: HashMap<Entity, (Vec2, Vec2)>,
+    /// Entities whose boxes span more than `MAX_CELLS_PER_BOX` cells.
+    oversized: HashSet<Entity>,
+}
+
+impl SpatialHash {
+    /// Fails unless `cell_size` is a finite number of pixels greater than zero.
+    pub fn new(cell_size: f32) -> Result<Self, String> {
+        if !(cell_size.is_finite() && cell_size > 0.0) {
+            return Err(format!("Spatial hash cell size must be positive, got {}", cell_size));
+        }
+        Ok(Self {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+            oversized: HashSet::new(),
+        })
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds.clear();
+        self.oversized.clear();
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.bounds.contains_key(&entity)
+    }
+
+    pub fn bounds(&self, entity: Entity) -> Option<(Vec2, Vec2)> {
+        self.bounds.get(&entity).copied()
+    }
+
+    /// Inserts an entity's box or moves it to a new one. Entities that stay within the
+    /// same cells aren't re-bucketed. Boxes that aren't finite are removed instead.
+    pub fn insert(&mut self, entity: Entity, min: Vec2, max: Vec2) {
+        if !min.is_finite() || !max.is_finite() {
+            self.remove(entity);
+            return;
+        }
+
+        let cells = self.grid_cells(min, max);
+        if let Some(old) = self.bounds.insert(entity, (min, max)) {
+            let old_cells = self.grid_cells(old.0, old.1);
+            if old_cells == cells {
+                return;
+            }
+            self.unlink(entity, old_cells);
+        }
+        match cells {
+            Some((start, end)) => {
+                for x in start.0..=end.0 {
+                    for y in start.1..=end.1 {
+                        self.cells.entry((x, y)).or_default().push(entity);
+                    }
+                }
+            }
+            None => {
+                self.oversized.insert(entity);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, entity: Entity) -> bool {
+        let Some((min, max)) = self.bounds.remove(&entity) else {
+            return false;
+        };
+        self.unlink(entity, self.grid_cells(min, max));
+        true
+    }
+
+    /// Removes every entity for which `keep` returns `false`.
+    pub fn retain(&mut self, mut keep: impl FnMut(Entity) -> bool) {
+        let removed: Vec<Entity> = self.bounds.keys().copied().filter(|entity| !keep(*entity)).collect();
+        for entity in removed {
+            self.remove(entity);
+        }
+    }
+
+    /// Entities whose boxes overlap `min..max`, sorted.
+    pub fn query_aabb(&self, min: Vec2, max: Vec2) -> Vec<Entity> {
+        self.query(min, max, |entity_min, entity_max| overlaps(min, max, entity_min, entity_max))
+    }
+
+    /// Entities whose boxes come closer than `radius` to `center`, sorted.
+    pub fn query_radius(&self, center: Vec2, radius: f32) -> Vec<Entity> {
+        let extent = Vec2::splat(radius);
+        self.query(center - extent, center + extent, |entity_min, entity_max| {
+            // In f64 so distances to far-away boxes don't overflow.
+            let offset = (center.clamp(entity_min, entity_max) - center).as_dvec2();
+            offset.length_squared() < f64::from(radius) * f64::from(radius)
+        })
+    }
+
+    /// Every pair of overlapping boxes, each reported once with the lower entity
+    /// first, sorted.
+    pub fn overlapping_pairs(&self) -> Vec<(Entity, Entity)> {
+        let mut pairs = Vec::new();
+        for (cell, entities) in &self.cells {
+            for (i, a) in entities.iter().enumerate() {
+                let (min_a, max_a) = self.bounds[a];
+                for b in &entities[i + 1..] {
+                    let (min_b, max_b) = self.bounds[b];
+                    // A pair shares every cell its intersection touches; only report it
+                    // from the cell holding the intersection's min corner.
+                    if overlaps(min_a, max_a, min_b, max_b) && self.cell_of(min_a.max(min_b)) == *cell {
+                        pairs.push(ordered(*a, *b));
+                    }
+                }
+            }
+        }
+        for a in &self.oversized {
+            let (min_a, max_a) = self.bounds[a];
+            for (b, (min_b, max_b)) in &self.bounds {
+                // Pairs of oversized boxes are reported from the lower entity only.
+                let reported_from_b = self.oversized.contains(b) && b.to_bits() <= a.to_bits();
+                if !reported_from_b && overlaps(min_a, max_a, *min_b, *max_b) {
+                    pairs.push(ordered(*a, *b));
+                }
+            }
+        }
+        pairs.sort_unstable_by_key(|(a, b)| (a.to_bits(), b.to_bits()));
+        pairs
+    }
+
+    fn query(&self, min: Vec2, max: Vec2, hit: impl Fn(Vec2, Vec2) -> bool) -> Vec<Entity> {
+        let (start, end) = self.cell_range(min, max);
+        let mut found = Vec::new();
+        if cell_count(start, end) > self.cells.len() as u64 {
+            // Checking every box is cheaper than visiting every cell of a huge range.
+            found.extend(
+                self.bounds
+                    .iter()
+                    .filter(|(_, (entity_min, entity_max))| hit(*entity_min, *entity_max))
+                    .map(|(entity, _)| *entity),
+            );
+        } else {
+            for x in start.0..=end.0 {
+                for y in start.1..=end.1 {
+                    let Some(entities) = self.cells.get(&(x, y)) else {
+                        continue;
+                    };
+                    found.extend(entities.iter().filter(|entity| {
+                        let (entity_min, entity_max) = self.bounds[*entity];
+                        hit(entity_min, entity_max)
+                    }));
+                }
+            }
+            found.extend(self.oversized.iter().filter(|entity| {
+                let (entity_min, entity_max) = self.bounds[*entity];
+                hit(entity_min, entity_max)
+            }));
+        }
+        found.sort_unstable_by_key(|entity| entity.to_bits());
+        found.dedup();
+        found
+    }
+
+    fn cell_of(&self, point: Vec2) -> Cell {
+        let cell = (point / self.cell_size).floor();
+        (cell.x as i32, cell.y as i32)
+    }
+
+    fn cell_range(&self, min: Vec2, max: Vec2) -> (Cell, Cell) {
+        (self.cell_of(min), self.cell_of(max))
+    }
+
+    /// Cells a box is stored in, or `None` if it spans too many and is kept in
+    /// `oversized` instead.
+    fn grid_cells(&self, min: Vec2, max: Vec2) -> Option<(Cell, Cell)> {
+        let (start, end) = self.cell_range(min, max);
+        (cell_count(start, end) <= MAX_CELLS_PER_BOX).then_some((start, end))
+    }
+
+    fn unlink(&mut self, entity: Entity, cells: Option<(Cell, Cell)>) {
+        let Some((start, end)) = cells else {
+            self.oversized.remove(&entity);
+            return;
+        };
+        for x in start.0..=end.0 {
+            for y in start.1..=end.1 {
+                if let Some(entities) = self.cells.get_mut(&(x, y)) {
+                    entities.retain(|other| *other != entity);
+                    if entities.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Default for SpatialHash {
+    fn default() -> Self {
+        Self {
+            cell_size: DEFAULT_CELL_SIZE,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+            oversized: HashSet::new(),
+        }
+    }
+}
+
+/// Number of cells from `start` to `end` inclusive, without overflowing on ranges that
+/// cover the whole grid.
+fn cell_count(start: Cell, end: Cell) -> u64 {
+    let span = |from: i32, to: i32| (i64::from(to) - i64::from(from) + 1).max(0) as u64;
+    span(start.0, end.0).saturating_mul(span(start.1, end.1))
+}
+
+fn ordered(a: Entity, b: Entity) -> (Entity, Entity) {
+    if a.to_bits() < b.to_bits() { (a, b) } else { (b, a) }
+}
+
+fn overlaps(min_a: Vec2, max_a: Vec2, min_b: Vec2, max_b: Vec2) -> bool {
+    max_a.x > min_b.x && min_a.x < max_b.x && max_a.y > min_b.y && min_a.y < max_b.y
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+/// Frames longer than this are clamped so a stall (breakpoint, window drag) doesn't
+/// trigger hundreds of catch-up simulation steps.
+const MAX_FRAME_DELTA: Duration = Duration::from_millis(250);
+
+/// Largest accepted `Time::time_scale`; keeps scaled frame times well within `Duration`.
+pub const MAX_TIME_SCALE: f32 = 100.0;
+
+/// Frame timing shared with systems. Simulation runs in fixed steps of
+/// [`Time::fixed_delta`]; rendering happens once per frame and interpolates between the
+/// last two simulation states using [`Time::interpolation_alpha`].
+#[derive(Clone, Debug)]
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+    frame_count: u64,
+    fixed_step_count: u64,
+    time_scale: f32,
+    fixed_delta: Duration,
+    accumulator: Duration,
+}
+
+impl Time {
+    pub fn new(fixed_delta: Duration) -> Self {
+        Self {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame_count: 0,
+            fixed_step_count: 0,
+            time_scale: 1.0,
+            fixed_delta,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Scaled time between the last two frames.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    /// Scaled time since the engine started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn elapsed_seconds(&self) -> f32 {
+        self.elapsed.as_secs_f32()
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn fixed_step_count(&self) -> u64 {
+        self.fixed_step_count
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Multiplier applied to real time; `0.0` pauses the simulation, `0.5` is slow motion.
+    /// Clamped to `0.0..=MAX_TIME_SCALE`; NaN and infinite scales are rejected.
+    pub fn set_time_scale(&mut self, time_scale: f32) -> Result<(), String> {
+        if !time_scale.is_finite() {
+            return Err(format!("Time scale must be finite, got {}", time_scale));
+        }
+        self.time_scale = time_scale.clamp(0.0, MAX_TIME_SCALE);
+        Ok(())
+    }
+
+    pub fn fixed_delta(&self) -> Duration {
+        self.fixed_delta
+    }
+
+    pub fn fixed_delta_seconds(&self) -> f32 {
+        self.fixed_delta.as_secs_f32()
+    }
+
+    /// The engine keeps this in sync with `PhysicsConfig::timestep`.
+    pub(crate) fn set_fixed_delta(&mut self, fixed_delta: Duration) {
+        self.fixed_delta = fixed_delta;
+    }
+
+    /// How far rendering is between the previous and the current simulation state, in `0.0..1.0`.
+    pub fn interpolation_alpha(&self) -> f32 {
+        if self.fixed_delta.is_zero() {
+            return 1.0;
+        }
+        (self.accumulator.as_secs_f64() / self.fixed_delta.as_secs_f64()) as f32
+    }
+
+    /// Starts a new frame that took `real_delta` of wall-clock time.
+    pub(crate) fn begin_frame(&mut self, real_delta: Duration) {
+        self.delta = real_delta.min(MAX_FRAME_DELTA).mul_f32(self.time_scale);
+        self.elapsed += self.delta;
+        self.accumulator += self.delta;
+        self.frame_count += 1;
+    }
+
+    /// Consumes one fixed step from the accumulator, returning `false` once there is
+    /// not enough accumulated time left for another step.
+    pub(crate) fn expend_fixed_step(&mut self) -> bool {
+        if self.fixed_delta.is_zero() || self.accumulator < self.fixed_delta {
+            return false;
+        }
+        self.accumulator -= self.fixed_delta;
+        self.fixed_step_count += 1;
+        true
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new(Duration::from_secs_f32(1.0 / 60.0))
+    }
+}
@@ -0,0 +1,34 @@
+use engine_2d::ecs::{Camera2D, MIN_CAMERA_ZOOM};
+use glam::{Vec2, Vec4};
+
+const TARGET: (u32, u32) = (800, 600);
+
+#[test]
+fn zero_and_negative_zoom_keep_the_projection_finite() {
+    for zoom in [0.0, -2.0] {
+        let mut camera = Camera2D::new(Vec2::new(10.0, 20.0));
+        camera.zoom = zoom;
+        assert_eq!(camera.effective_zoom(), MIN_CAMERA_ZOOM);
+        assert!(camera.view_projection(TARGET).is_finite());
+        let world = camera.screen_to_world(Vec2::new(400.0, 300.0), TARGET);
+        assert!(world.abs_diff_eq(Vec2::new(10.0, 20.0), 1e-2), "{world}");
+    }
+}
+
+#[test]
+fn projection_uses_the_clamped_viewport() {
+    let mut camera = Camera2D::new(Vec2::ZERO);
+    // Right half of the target, overhanging it by another half.
+    camera.viewport = Vec4::new(0.5, 0.0, 1.0, 1.0);
+    assert_eq!(camera.viewport_rect(TARGET), Some((400.0, 0.0, 400.0, 600.0)));
+    assert_eq!(camera.viewport_size(TARGET), Vec2::new(400.0, 600.0));
+
+    // The centre of the visible rectangle shows the camera position, and its right
+    // edge is half the visible width away.
+    assert!(camera.screen_to_world(Vec2::new(600.0, 300.0), TARGET).abs_diff_eq(Vec2::ZERO, 1e-3));
+    assert!(camera.screen_to_world(Vec2::new(800.0, 300.0), TARGET).abs_diff_eq(Vec2::new(200.0, 0.0), 1e-3));
+
+    camera.viewport = Vec4::new(1.5, 0.0, 1.0, 1.0);
+    assert_eq!(camera.viewport_rect(TARGET), None);
+    assert_eq!(camera.viewport_size(TARGET), Vec2::ZERO);
+}
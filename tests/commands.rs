@@ -0,0 +1,116 @@
+use engine_2d::ecs::{CommandFailed, Commands, EcsWorld, Resources, RigidBody, Transform};
+use engine_2d::schedule::{Schedule, Stage, System};
+use glam::Vec2;
+use hecs::World;
+
+fn spawn(ecs: &mut EcsWorld, x: f32) -> hecs::Entity {
+    ecs.world.spawn((Transform { position: Vec2::new(x, 0.0), rotation: 0.0, scale: Vec2::ONE },))
+}
+
+#[test]
+fn commands_change_the_world_while_a_query_is_borrowed() {
+    let mut ecs = EcsWorld::new();
+    let keep = spawn(&mut ecs, 10.0);
+    let doomed = spawn(&mut ecs, -10.0);
+
+    let mut spawned = Vec::new();
+    {
+        let mut commands = ecs.commands();
+        for (entity, transform) in ecs.world.query::<&Transform>().iter() {
+            if transform.position.x < 0.0 {
+                commands.despawn(entity);
+            } else {
+                spawned.push(commands.spawn(&ecs.world, (*transform, 1u32)));
+                commands.insert(entity, (RigidBody::new(Vec2::ZERO, 1.0),));
+            }
+        }
+        assert_eq!(commands.len(), 3);
+    }
+    // Nothing happens until the flush.
+    assert!(ecs.world.contains(doomed));
+
+    ecs.apply_commands().unwrap();
+    assert!(!ecs.world.contains(doomed));
+    assert!(ecs.world.get::<&RigidBody>(keep).is_ok());
+    assert_eq!(*ecs.world.get::<&u32>(spawned[0]).unwrap(), 1);
+    assert!(ecs.commands().is_empty());
+}
+
+#[test]
+fn failing_commands_are_reported_without_stopping_the_rest() {
+    let mut world = World::new();
+    let entity = world.spawn((1u32,));
+    let gone = world.spawn((2u32,));
+    world.despawn(gone).unwrap();
+
+    let mut commands = Commands::new();
+    commands.despawn(gone);
+    commands.remove::<(f32,)>(entity);
+    commands.insert(entity, ("tagged",));
+    commands.add(|_world| Err("custom failure".to_string()));
+
+    let errors = commands.apply(&mut world).unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert!(errors[0].contains("despawn"), "{}", errors[0]);
+    assert!(errors[1].contains("f32"), "{}", errors[1]);
+    assert_eq!(errors[2], "custom failure");
+    assert_eq!(*world.get::<&&str>(entity).unwrap(), "tagged");
+}
+
+#[test]
+fn stages_flush_queued_commands_and_send_failures_as_events() {
+    let mut ecs = EcsWorld::new();
+    let parent = spawn(&mut ecs, 0.0);
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(
+            Stage::Update,
+            System::new("spawn_children", move |world: &mut World, resources: &Resources| {
+                let mut commands = resources.get_mut::<Commands>().unwrap();
+                let child = commands.spawn(world, (Transform { position: Vec2::ZERO, rotation: 0.0, scale: Vec2::ONE },));
+                commands.set_parent(child, parent);
+                commands.set_parent(parent, child);
+            }),
+        )
+        .unwrap();
+    schedule
+        .add_system(
+            Stage::Update,
+            System::new("sees_nothing_yet", move |world: &mut World, _resources: &Resources| {
+                assert_eq!(world.len(), 1);
+            })
+            .after("spawn_children"),
+        )
+        .unwrap();
+    let mut failures = ecs.events::<CommandFailed>().unwrap().reader();
+
+    schedule.run_stage(Stage::Update, &mut ecs.world, &ecs.resources);
+
+    let children = ecs.children(parent);
+    assert_eq!(children.len(), 1);
+    assert_eq!(ecs.parent(children[0]), Some(parent));
+    let events = ecs.events::<CommandFailed>().unwrap();
+    let errors: Vec<_> = failures.read(&events).collect();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].0.contains("descendant"), "{}", errors[0].0);
+}
+
+#[test]
+fn helpers_return_errors_for_missing_entities() {
+    let mut ecs = EcsWorld::new();
+    let entity = spawn(&mut ecs, 0.0);
+    ecs.despawn(entity).unwrap();
+
+    let error = ecs.add_rigid_body(entity, Vec2::ZERO, 1.0).unwrap_err();
+    assert!(error.contains("RigidBody"), "{error}");
+    assert!(ecs.add_collider(entity, 1.0, 1.0, false).is_err());
+    assert!(ecs.apply_impulse(entity, Vec2::X).is_err());
+
+    // Helpers that change an existing component fail when it's missing.
+    let entity = spawn(&mut ecs, 0.0);
+    let error = ecs.set_velocity(entity, Vec2::X).unwrap_err();
+    assert!(error.contains("set velocity"), "{error}");
+    ecs.add_rigid_body(entity, Vec2::ZERO, 1.0).unwrap();
+    ecs.set_velocity(entity, Vec2::X).unwrap();
+    assert_eq!(ecs.velocity(entity), Some(Vec2::X));
+}
@@ -0,0 +1,140 @@
+use engine_2d::ecs::{EcsWorld, Events, OverlapEvent, Resources};
+use engine_2d::engine::{Engine, SceneChanged};
+use engine_2d::physics::PhysicsEvent;
+use engine_2d::resources::AssetLoaded;
+use engine_2d::schedule::{Stage, System};
+use glam::Vec2;
+use hecs::World;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+fn headless_engine() -> Engine<'static> {
+    pollster::block_on(Engine::new_headless(64, 64)).unwrap()
+}
+
+fn one_step(engine: &Engine) -> Duration {
+    Duration::from_secs_f32(engine.physics_system().config().timestep)
+}
+
+#[test]
+fn events_last_two_frames() {
+    let mut events = Events::new();
+    events.send(1);
+    events.update();
+    events.send(2);
+    assert_eq!(events.iter().copied().collect::<Vec<_>>(), [1, 2]);
+
+    events.update();
+    assert_eq!(events.iter().copied().collect::<Vec<_>>(), [2]);
+    events.update();
+    assert!(events.is_empty());
+}
+
+#[test]
+fn readers_keep_their_own_cursor() {
+    let mut events = Events::new();
+    let mut early = events.reader();
+    events.send("a");
+    events.send("b");
+    let mut late = events.reader_from_now();
+    assert_eq!(early.len(&events), 2);
+    assert_eq!(early.read(&events).copied().collect::<Vec<_>>(), ["a", "b"]);
+    assert!(early.is_empty(&events));
+
+    events.update();
+    events.send("c");
+    assert_eq!(early.read(&events).copied().collect::<Vec<_>>(), ["c"]);
+    assert_eq!(late.read(&events).copied().collect::<Vec<_>>(), ["c"]);
+    assert_eq!(late.read(&events).count(), 0);
+
+    // Events dropped before a reader got to them are skipped.
+    let mut stale = events.reader();
+    events.send("d");
+    events.update();
+    events.update();
+    events.send("e");
+    assert_eq!(stale.read(&events).copied().collect::<Vec<_>>(), ["e"]);
+}
+
+#[test]
+fn world_events_are_advanced_by_update_events() {
+    let mut ecs = EcsWorld::new();
+    ecs.add_event::<u32>();
+    ecs.send_event(7u32);
+
+    engine_2d::ecs::update_events(&mut ecs.world, &ecs.resources);
+    assert_eq!(ecs.events::<u32>().unwrap().len(), 1);
+    engine_2d::ecs::update_events(&mut ecs.world, &ecs.resources);
+    assert!(ecs.events::<u32>().unwrap().is_empty());
+}
+
+#[test]
+fn overlaps_are_reported_when_they_start_and_end() {
+    let mut ecs = EcsWorld::new();
+    let a = ecs.create_sprite_entity("player", Vec2::ZERO, 20.0, 20.0);
+    ecs.add_collider(a, 20.0, 20.0, false).unwrap();
+    let b = ecs.create_sprite_entity("player", Vec2::new(15.0, 0.0), 20.0, 20.0);
+    ecs.add_collider(b, 20.0, 20.0, false).unwrap();
+    let (a, b) = if a.to_bits() < b.to_bits() { (a, b) } else { (b, a) };
+    let mut reader = ecs.events::<OverlapEvent>().unwrap().reader();
+
+    engine_2d::ecs::detect_collider_overlaps(&mut ecs.world, &ecs.resources);
+    engine_2d::ecs::detect_collider_overlaps(&mut ecs.world, &ecs.resources);
+    let events = ecs.events::<OverlapEvent>().unwrap();
+    assert_eq!(reader.read(&events).copied().collect::<Vec<_>>(), [OverlapEvent::Started { a, b }]);
+    drop(events);
+
+    ecs.world.get::<&mut engine_2d::ecs::Transform>(b).unwrap().position.x = 100.0;
+    engine_2d::ecs::detect_collider_overlaps(&mut ecs.world, &ecs.resources);
+    let events = ecs.events::<OverlapEvent>().unwrap();
+    assert_eq!(reader.read(&events).copied().collect::<Vec<_>>(), [OverlapEvent::Ended { a, b }]);
+}
+
+#[test]
+fn engine_sends_collision_scene_and_asset_events() {
+    let mut engine = headless_engine();
+    engine.load_scene("drop", |ecs| {
+        let ground = ecs.create_sprite_entity("player", Vec2::ZERO, 400.0, 20.0);
+        ecs.add_collider(ground, 400.0, 20.0, false).unwrap();
+        let ball = ecs.create_sprite_entity("player", Vec2::new(0.0, 30.0), 10.0, 10.0);
+        ecs.add_rigid_body(ball, Vec2::ZERO, 1.0).unwrap();
+        ecs.add_collider(ball, 10.0, 10.0, false).unwrap();
+    });
+    let texture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/demo_scene.png");
+    engine.resource_manager_mut().load_texture("reference", texture).unwrap();
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = seen.clone();
+    let mut scenes = engine.ecs_world().events::<SceneChanged>().unwrap().reader();
+    let mut assets = engine.ecs_world().events::<AssetLoaded>().unwrap().reader();
+    let mut collisions = engine.ecs_world().events::<PhysicsEvent>().unwrap().reader();
+    engine
+        .add_system(
+            Stage::Update,
+            System::new("listen", move |_world: &mut World, resources: &Resources| {
+                let mut log = log.borrow_mut();
+                for scene in scenes.read(&resources.get::<Events<SceneChanged>>().unwrap()) {
+                    log.push(format!("scene {}", scene.name));
+                }
+                for asset in assets.read(&resources.get::<Events<AssetLoaded>>().unwrap()) {
+                    log.push(format!("asset {}", asset.name));
+                }
+                for event in collisions.read(&resources.get::<Events<PhysicsEvent>>().unwrap()) {
+                    if let PhysicsEvent::CollisionStarted { .. } = event {
+                        log.push("collision".to_string());
+                    }
+                }
+            }),
+        )
+        .unwrap();
+
+    let step = one_step(&engine);
+    for _ in 0..60 {
+        engine.advance(step);
+    }
+
+    assert_eq!(*seen.borrow(), ["scene drop", "asset reference", "collision"]);
+    // Two frames later nothing is left.
+    assert!(engine.ecs_world().events::<SceneChanged>().unwrap().is_empty());
+}
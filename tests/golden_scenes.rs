@@ -0,0 +1,53 @@
+use engine_2d::demo_scene::load_demo_scene;
+use engine_2d::golden::{GoldenImageTest, compare_images};
+use glam::Vec2;
+use image::{Rgba, RgbaImage};
+
+const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+#[test]
+fn demo_scene_matches_reference() {
+    GoldenImageTest::new("demo_scene", GOLDEN_DIR)
+        .size(800, 600)
+        .frames(60)
+        .run(load_demo_scene)
+        .unwrap();
+}
+
+#[test]
+fn demo_scene_with_physics_debug_overlay_matches_reference() {
+    GoldenImageTest::new("demo_scene_physics_debug", GOLDEN_DIR)
+        .size(800, 600)
+        .frames(60)
+        .physics_debug(true)
+        .run(load_demo_scene)
+        .unwrap();
+}
+
+#[test]
+fn demo_scene_through_zoomed_camera_matches_reference() {
+    GoldenImageTest::new("demo_scene_zoomed_camera", GOLDEN_DIR)
+        .size(800, 600)
+        .frames(60)
+        .run(|world| {
+            load_demo_scene(world);
+            let camera = world.create_camera(Vec2::new(100.0, 200.0));
+            world.world.get::<&mut engine_2d::ecs::Camera2D>(camera).unwrap().zoom = 0.5;
+        })
+        .unwrap();
+}
+
+#[test]
+fn compare_images_reports_pixels_outside_tolerance() {
+    let expected = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+    let mut actual = expected.clone();
+    actual.put_pixel(1, 1, Rgba([102, 100, 100, 255]));
+    actual.put_pixel(2, 2, Rgba([110, 100, 100, 255]));
+
+    let comparison = compare_images(&actual, &expected, 2).unwrap();
+    assert_eq!(comparison.mismatched_pixels, 1);
+    assert_eq!(comparison.max_channel_difference, 10);
+    assert_eq!(*comparison.diff_image.get_pixel(2, 2), Rgba([255, 0, 0, 255]));
+
+    assert!(compare_images(&actual, &RgbaImage::new(2, 2), 2).is_err());
+}
@@ -0,0 +1,229 @@
+use engine_2d::ecs::{propagate_global_transforms, Children, EcsWorld, GlobalTransform, Parent, Transform};
+use engine_2d::physics::{PhysicsConfig, PhysicsQueryFilter, PhysicsSystem};
+use glam::Vec2;
+use hecs::Entity;
+use std::f32::consts::FRAC_PI_2;
+
+fn spawn(ecs: &mut EcsWorld, position: Vec2) -> Entity {
+    ecs.world.spawn((Transform { position, rotation: 0.0, scale: Vec2::ONE },))
+}
+
+fn global(ecs: &EcsWorld, entity: Entity) -> GlobalTransform {
+    *ecs.world.get::<&GlobalTransform>(entity).unwrap()
+}
+
+fn assert_near(actual: Vec2, expected: Vec2) {
+    assert!(actual.abs_diff_eq(expected, 1e-3), "{actual} != {expected}");
+}
+
+#[test]
+fn children_inherit_position_rotation_and_non_uniform_scale() {
+    let mut ecs = EcsWorld::new();
+    let parent = ecs.world.spawn((Transform {
+        position: Vec2::new(100.0, 0.0),
+        rotation: FRAC_PI_2,
+        scale: Vec2::new(2.0, 1.0),
+    },));
+    let child = spawn(&mut ecs, Vec2::new(10.0, 0.0));
+    let grandchild = ecs.world.spawn((Transform {
+        position: Vec2::new(0.0, 5.0),
+        rotation: -FRAC_PI_2,
+        scale: Vec2::ONE,
+    },));
+    ecs.set_parent(child, parent).unwrap();
+    ecs.set_parent(grandchild, child).unwrap();
+
+    propagate_global_transforms(&mut ecs.world);
+
+    assert_near(global(&ecs, parent).translation(), Vec2::new(100.0, 0.0));
+    // Scaled along the parent's x axis, then rotated a quarter turn.
+    assert_near(global(&ecs, child).translation(), Vec2::new(100.0, 20.0));
+    // The parent's y axis isn't scaled.
+    assert_near(global(&ecs, grandchild).translation(), Vec2::new(95.0, 20.0));
+    assert!(global(&ecs, grandchild).rotation().abs() < 1e-5);
+    assert_near(global(&ecs, grandchild).transform_point(Vec2::new(1.0, 0.0)), Vec2::new(96.0, 20.0));
+}
+
+#[test]
+fn deep_hierarchies_accumulate_without_recursion() {
+    let mut ecs = EcsWorld::new();
+    let root = spawn(&mut ecs, Vec2::ZERO);
+    let mut last = root;
+    for _ in 0..2_000 {
+        let child = spawn(&mut ecs, Vec2::new(1.0, 0.5));
+        ecs.set_parent(child, last).unwrap();
+        last = child;
+    }
+
+    propagate_global_transforms(&mut ecs.world);
+    assert_near(global(&ecs, last).translation(), Vec2::new(2_000.0, 1_000.0));
+
+    ecs.world.get::<&mut Transform>(root).unwrap().position = Vec2::new(-2_000.0, 0.0);
+    propagate_global_transforms(&mut ecs.world);
+    assert_near(global(&ecs, last).translation(), Vec2::new(0.0, 1_000.0));
+}
+
+#[test]
+fn reparenting_moves_the_child_between_parents() {
+    let mut ecs = EcsWorld::new();
+    let left = spawn(&mut ecs, Vec2::new(-100.0, 0.0));
+    let right = spawn(&mut ecs, Vec2::new(100.0, 0.0));
+    let gun = spawn(&mut ecs, Vec2::new(5.0, 0.0));
+
+    ecs.set_parent(gun, left).unwrap();
+    propagate_global_transforms(&mut ecs.world);
+    assert_near(global(&ecs, gun).translation(), Vec2::new(-95.0, 0.0));
+
+    ecs.set_parent(gun, right).unwrap();
+    assert_eq!(ecs.parent(gun), Some(right));
+    assert!(ecs.children(left).is_empty());
+    assert_eq!(ecs.children(right), [gun]);
+    propagate_global_transforms(&mut ecs.world);
+    assert_near(global(&ecs, gun).translation(), Vec2::new(105.0, 0.0));
+
+    assert_eq!(ecs.remove_parent(gun), Some(right));
+    assert!(ecs.world.get::<&Parent>(gun).is_err());
+    assert_eq!(*ecs.world.get::<&Children>(right).unwrap(), Children(Vec::new()));
+    propagate_global_transforms(&mut ecs.world);
+    assert_near(global(&ecs, gun).translation(), Vec2::new(5.0, 0.0));
+}
+
+#[test]
+fn parenting_cycles_are_rejected() {
+    let mut ecs = EcsWorld::new();
+    let a = spawn(&mut ecs, Vec2::ZERO);
+    let b = spawn(&mut ecs, Vec2::ZERO);
+    let c = spawn(&mut ecs, Vec2::ZERO);
+    ecs.set_parent(b, a).unwrap();
+    ecs.set_parent(c, b).unwrap();
+
+    assert!(ecs.set_parent(a, c).is_err());
+    assert!(ecs.set_parent(a, a).is_err());
+    assert_eq!(ecs.parent(a), None);
+    assert_eq!(ecs.children(c), []);
+}
+
+#[test]
+fn attaching_below_an_existing_cycle_fails() {
+    let mut ecs = EcsWorld::new();
+    let a = spawn(&mut ecs, Vec2::ZERO);
+    let b = spawn(&mut ecs, Vec2::ZERO);
+    let c = spawn(&mut ecs, Vec2::ZERO);
+    ecs.world.insert_one(a, Parent(b)).unwrap();
+    ecs.world.insert_one(b, Parent(a)).unwrap();
+
+    assert!(ecs.set_parent(c, a).is_err());
+    assert_eq!(ecs.parent(c), None);
+}
+
+#[test]
+fn despawning_recursively_removes_the_whole_subtree() {
+    let mut ecs = EcsWorld::new();
+    let root = spawn(&mut ecs, Vec2::ZERO);
+    let player = spawn(&mut ecs, Vec2::ZERO);
+    let gun = spawn(&mut ecs, Vec2::ZERO);
+    let muzzle = spawn(&mut ecs, Vec2::ZERO);
+    let sibling = spawn(&mut ecs, Vec2::ZERO);
+    ecs.set_parent(player, root).unwrap();
+    ecs.set_parent(gun, player).unwrap();
+    ecs.set_parent(muzzle, gun).unwrap();
+    ecs.set_parent(sibling, root).unwrap();
+
+    ecs.despawn_recursive(player).unwrap();
+
+    assert!(!ecs.world.contains(player) && !ecs.world.contains(gun) && !ecs.world.contains(muzzle));
+    assert_eq!(ecs.children(root), [sibling]);
+    assert!(ecs.despawn_recursive(player).is_err());
+}
+
+#[test]
+fn sprites_render_at_their_global_transform() {
+    let mut ecs = EcsWorld::new();
+    let parent = ecs.create_sprite_entity("player", Vec2::new(50.0, 50.0), 10.0, 10.0);
+    let child = ecs.create_sprite_entity("enemy", Vec2::new(0.0, 20.0), 10.0, 10.0);
+    ecs.set_parent(child, parent).unwrap();
+    propagate_global_transforms(&mut ecs.world);
+
+    let renderables = ecs.get_renderables(1.0);
+    let (_, child_transform, _) = renderables.iter().find(|(entity, _, _)| *entity == child).unwrap();
+    assert_near(child_transform.translation(), Vec2::new(50.0, 70.0));
+}
+
+#[test]
+fn physics_simulates_children_in_world_space() {
+    let mut ecs = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+    let platform = spawn(&mut ecs, Vec2::new(300.0, 0.0));
+    let wall = spawn(&mut ecs, Vec2::new(0.0, 40.0));
+    ecs.add_collider(wall, 20.0, 20.0, false).unwrap();
+    ecs.set_parent(wall, platform).unwrap();
+    let crate_body = spawn(&mut ecs, Vec2::new(-300.0, 200.0));
+    ecs.add_rigid_body(crate_body, Vec2::ZERO, 1.0).unwrap();
+    ecs.add_collider(crate_body, 10.0, 10.0, false).unwrap();
+    ecs.set_parent(crate_body, platform).unwrap();
+
+    for _ in 0..30 {
+        propagate_global_transforms(&mut ecs.world);
+        physics.update(&mut ecs.world, 1.0 / 60.0);
+    }
+    propagate_global_transforms(&mut ecs.world);
+
+    let hit = physics
+        .cast_ray(Vec2::new(300.0, 200.0), Vec2::new(0.0, -1.0), 500.0, PhysicsQueryFilter::new())
+        .unwrap();
+    assert_eq!(hit.entity, wall);
+    assert_near(hit.point, Vec2::new(300.0, 50.0));
+
+    // The falling body's local transform stays relative to the platform.
+    let local = *ecs.world.get::<&Transform>(crate_body).unwrap();
+    assert_eq!(local.position.x, -300.0);
+    assert!(local.position.y < 200.0);
+    assert_near(global(&ecs, crate_body).translation(), local.position + Vec2::new(300.0, 0.0));
+}
+
+/// A weightless body drifting right at 120 px/s, and a physics system to move it.
+fn drifting_parent(ecs: &mut EcsWorld) -> (PhysicsSystem, Entity) {
+    let physics = PhysicsSystem::with_config(PhysicsConfig { gravity: Vec2::ZERO, ..PhysicsConfig::default() }).unwrap();
+    let parent = spawn(ecs, Vec2::ZERO);
+    ecs.add_rigid_body(parent, Vec2::new(120.0, 0.0), 1.0).unwrap();
+    (physics, parent)
+}
+
+fn simulate(ecs: &mut EcsWorld, physics: &mut PhysicsSystem, steps: usize) {
+    for _ in 0..steps {
+        propagate_global_transforms(&mut ecs.world);
+        physics.update(&mut ecs.world, 1.0 / 60.0);
+    }
+    propagate_global_transforms(&mut ecs.world);
+}
+
+#[test]
+fn child_bodies_stay_relative_to_a_moving_parent() {
+    let mut ecs = EcsWorld::new();
+    let (mut physics, parent) = drifting_parent(&mut ecs);
+    let child = spawn(&mut ecs, Vec2::new(0.0, 50.0));
+    ecs.add_rigid_body(child, Vec2::new(120.0, 0.0), 1.0).unwrap();
+    ecs.set_parent(child, parent).unwrap();
+
+    simulate(&mut ecs, &mut physics, 60);
+
+    let parent_position = global(&ecs, parent).translation();
+    assert!((parent_position.x - 120.0).abs() < 0.5, "{parent_position}");
+    assert!(ecs.world.get::<&Transform>(child).unwrap().position.abs_diff_eq(Vec2::new(0.0, 50.0), 1e-2));
+    assert!(global(&ecs, child).translation().abs_diff_eq(parent_position + Vec2::new(0.0, 50.0), 1e-2));
+}
+
+#[test]
+fn child_colliders_follow_a_moving_parent() {
+    let mut ecs = EcsWorld::new();
+    let (mut physics, parent) = drifting_parent(&mut ecs);
+    let sensor = spawn(&mut ecs, Vec2::new(0.0, 50.0));
+    ecs.add_collider(sensor, 10.0, 10.0, true).unwrap();
+    ecs.set_parent(sensor, parent).unwrap();
+
+    simulate(&mut ecs, &mut physics, 60);
+
+    let expected = global(&ecs, parent).translation() + Vec2::new(0.0, 50.0);
+    assert_eq!(physics.entities_at_point(expected, PhysicsQueryFilter::new()), vec![sensor]);
+    assert!(physics.entities_at_point(Vec2::new(0.0, 50.0), PhysicsQueryFilter::new()).is_empty());
+}
@@ -0,0 +1,894 @@
+use engine_2d::ecs::{
+    BodyType, CharacterController, Collider, CombineRule, EcsWorld, ExternalForce, ExternalImpulse, Joint, JointKind, JointMotor,
+    OneWayPlatform, PhysicsMaterial, RigidBody, Transform,
+};
+use engine_2d::physics::{PhysicsConfig, PhysicsEvent, PhysicsQueryFilter, PhysicsSystem};
+use engine_2d::layers::CollisionLayers;
+use engine_2d::shapes::ColliderShape;
+use glam::Vec2;
+
+const DT: f32 = 1.0 / 60.0;
+
+fn step(physics: &mut PhysicsSystem, world: &mut EcsWorld, steps: usize) {
+    for _ in 0..steps {
+        physics.update(&mut world.world, DT);
+    }
+}
+
+fn position(world: &EcsWorld, entity: hecs::Entity) -> Vec2 {
+    world.world.get::<&Transform>(entity).unwrap().position
+}
+
+#[test]
+fn spawned_bodies_are_simulated_and_written_back() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let entity = world.create_sprite_entity("player", Vec2::new(0.0, 100.0), 10.0, 10.0);
+    world.add_rigid_body(entity, Vec2::new(30.0, 0.0), 1.0).unwrap();
+    world.add_collider(entity, 10.0, 10.0, false).unwrap();
+
+    step(&mut physics, &mut world, 60);
+
+    assert!(physics.body_handle(entity).is_some());
+    assert!(physics.collider_handle(entity).is_some());
+    let position = position(&world, entity);
+    assert!((position.x - 30.0).abs() < 0.5, "x = {}", position.x);
+    assert!(position.y < 100.0, "gravity should pull the body down, y = {}", position.y);
+}
+
+#[test]
+fn bodies_rest_on_static_colliders() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let ground = world.create_sprite_entity("enemy", Vec2::new(0.0, 0.0), 200.0, 20.0);
+    world.add_collider(ground, 200.0, 20.0, false).unwrap();
+    let crate_entity = world.create_sprite_entity("player", Vec2::new(0.0, 20.0), 10.0, 10.0);
+    world.add_rigid_body(crate_entity, Vec2::ZERO, 1.0).unwrap();
+    world.add_collider(crate_entity, 10.0, 10.0, false).unwrap();
+
+    step(&mut physics, &mut world, 300);
+
+    assert!((position(&world, crate_entity).y - 15.0).abs() < 0.5);
+    assert_eq!(position(&world, ground), Vec2::ZERO);
+}
+
+#[test]
+fn transform_edits_teleport_the_body() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let entity = world.create_sprite_entity("player", Vec2::ZERO, 10.0, 10.0);
+    world.add_rigid_body(entity, Vec2::ZERO, 1.0).unwrap();
+    step(&mut physics, &mut world, 1);
+
+    world.world.get::<&mut Transform>(entity).unwrap().position = Vec2::new(500.0, 0.0);
+    step(&mut physics, &mut world, 1);
+
+    assert!((position(&world, entity).x - 500.0).abs() < 1e-3);
+}
+
+#[test]
+fn static_colliders_follow_transform_and_collider_edits() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+    let wall = world.create_sprite_entity("enemy", Vec2::ZERO, 10.0, 10.0);
+    world.add_collider(wall, 10.0, 10.0, false).unwrap();
+    step(&mut physics, &mut world, 1);
+    let at = |physics: &PhysicsSystem, point: Vec2| physics.entities_at_point(point, PhysicsQueryFilter::new()) == vec![wall];
+
+    world.world.get::<&mut Transform>(wall).unwrap().position = Vec2::new(100.0, 0.0);
+    step(&mut physics, &mut world, 1);
+    assert!(at(&physics, Vec2::new(100.0, 0.0)));
+    assert!(!at(&physics, Vec2::ZERO));
+
+    world.world.get::<&mut Transform>(wall).unwrap().scale = Vec2::splat(3.0);
+    step(&mut physics, &mut world, 1);
+    assert!(at(&physics, Vec2::new(112.0, 0.0)));
+
+    world.world.get::<&mut Collider>(wall).unwrap().shape = ColliderShape::Ball { radius: 1.0 };
+    step(&mut physics, &mut world, 1);
+    assert!(at(&physics, Vec2::new(102.5, 0.0)));
+    assert!(!at(&physics, Vec2::new(102.5, 2.5)));
+
+    world.world.get::<&mut Collider>(wall).unwrap().is_trigger = true;
+    step(&mut physics, &mut world, 1);
+    assert!(at(&physics, Vec2::new(100.0, 0.0)));
+    assert!(physics.entities_at_point(Vec2::new(100.0, 0.0), PhysicsQueryFilter::new().exclude_sensors()).is_empty());
+}
+
+#[test]
+fn despawned_entities_are_removed_from_physics() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let entity = world.create_sprite_entity("player", Vec2::ZERO, 10.0, 10.0);
+    world.add_rigid_body(entity, Vec2::ZERO, 1.0).unwrap();
+    world.add_collider(entity, 10.0, 10.0, false).unwrap();
+    step(&mut physics, &mut world, 1);
+    let collider = physics.collider_handle(entity).unwrap();
+    assert_eq!(physics.collider_entity(collider), Some(entity));
+
+    world.despawn(entity).unwrap();
+    step(&mut physics, &mut world, 1);
+
+    assert!(physics.body_handle(entity).is_none());
+    assert_eq!(physics.collider_entity(collider), None);
+}
+
+#[test]
+fn collisions_report_start_and_end_events() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let ground = world.create_sprite_entity("enemy", Vec2::ZERO, 200.0, 20.0);
+    world.add_collider(ground, 200.0, 20.0, false).unwrap();
+    let body = world.create_sprite_entity("player", Vec2::new(0.0, 30.0), 10.0, 10.0);
+    world.add_rigid_body(body, Vec2::new(0.0, -60.0), 1.0).unwrap();
+    world.add_collider(body, 10.0, 10.0, false).unwrap();
+
+    step(&mut physics, &mut world, 60);
+    let events: Vec<PhysicsEvent> = physics.drain_events().collect();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        PhysicsEvent::CollisionStarted { a, b } if (a, b) == (ground, body) || (a, b) == (body, ground)
+    ));
+    assert!(physics.events().is_empty());
+
+    world.despawn(body).unwrap();
+    step(&mut physics, &mut world, 1);
+    assert!(matches!(physics.events(), [PhysicsEvent::CollisionEnded { .. }]));
+}
+
+#[test]
+fn triggers_report_enter_and_exit_events() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::with_config(PhysicsConfig {
+        gravity: Vec2::ZERO,
+        ..PhysicsConfig::default()
+    })
+    .unwrap();
+
+    let zone = world.create_sprite_entity("enemy", Vec2::new(50.0, 0.0), 20.0, 20.0);
+    world.add_collider(zone, 20.0, 20.0, true).unwrap();
+    let body = world.create_sprite_entity("player", Vec2::ZERO, 10.0, 10.0);
+    world.add_rigid_body(body, Vec2::new(100.0, 0.0), 1.0).unwrap();
+    world.add_collider(body, 10.0, 10.0, false).unwrap();
+
+    step(&mut physics, &mut world, 60);
+
+    let events: Vec<PhysicsEvent> = physics.drain_events().collect();
+    assert_eq!(
+        events,
+        vec![
+            PhysicsEvent::TriggerEntered { trigger: zone, other: body },
+            PhysicsEvent::TriggerExited { trigger: zone, other: body },
+        ]
+    );
+}
+
+/// A static floor spanning x in [-100, 100] with its top at y = 10, and a dynamic
+/// box resting on it at x = 50.
+fn query_scene() -> (EcsWorld, PhysicsSystem, hecs::Entity, hecs::Entity) {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let floor = world.create_sprite_entity("enemy", Vec2::ZERO, 200.0, 20.0);
+    world.add_collider(floor, 200.0, 20.0, false).unwrap();
+    let block = world.create_sprite_entity("player", Vec2::new(50.0, 15.0), 10.0, 10.0);
+    world.add_rigid_body(block, Vec2::ZERO, 1.0).unwrap();
+    world.add_collider(block, 10.0, 10.0, false).unwrap();
+    step(&mut physics, &mut world, 1);
+
+    (world, physics, floor, block)
+}
+
+#[test]
+fn ray_casts_report_entity_point_and_normal() {
+    let (_world, physics, floor, block) = query_scene();
+
+    let hit = physics
+        .cast_ray(Vec2::new(0.0, 100.0), Vec2::new(0.0, -1.0), 500.0, PhysicsQueryFilter::new())
+        .unwrap();
+    assert_eq!(hit.entity, floor);
+    assert!((hit.point.y - 10.0).abs() < 1e-3);
+    assert!((hit.normal - Vec2::Y).length() < 1e-3);
+    assert!((hit.distance - 90.0).abs() < 1e-3);
+
+    let hits = physics.cast_ray_all(Vec2::new(50.0, 100.0), Vec2::new(0.0, -1.0), 500.0, PhysicsQueryFilter::new());
+    let entities: Vec<_> = hits.iter().map(|hit| hit.entity).collect();
+    assert_eq!(entities, vec![block, floor]);
+
+    let filtered = physics
+        .cast_ray(Vec2::new(50.0, 100.0), Vec2::new(0.0, -1.0), 500.0, PhysicsQueryFilter::new().exclude_entity(block))
+        .unwrap();
+    assert_eq!(filtered.entity, floor);
+
+    assert!(physics.cast_ray(Vec2::new(0.0, 100.0), Vec2::Y, 500.0, PhysicsQueryFilter::new()).is_none());
+}
+
+#[test]
+fn shape_casts_detect_ground_below() {
+    let (_world, physics, floor, _block) = query_scene();
+
+    let hit = physics
+        .cast_shape(
+            &ColliderShape::Ball { radius: 5.0 },
+            Vec2::new(-50.0, 40.0),
+            0.0,
+            Vec2::new(0.0, -1.0),
+            100.0,
+            PhysicsQueryFilter::new(),
+        )
+        .unwrap();
+    assert_eq!(hit.entity, floor);
+    assert!((hit.distance - 25.0).abs() < 1e-2);
+    assert!((hit.point - Vec2::new(-50.0, 10.0)).length() < 1e-2);
+    assert!((hit.normal - Vec2::Y).length() < 1e-3);
+}
+
+#[test]
+fn point_and_area_queries_return_entities() {
+    let (_world, physics, floor, block) = query_scene();
+
+    assert_eq!(physics.entities_at_point(Vec2::new(50.0, 15.0), PhysicsQueryFilter::new()), vec![block]);
+    assert!(physics.entities_at_point(Vec2::new(0.0, 50.0), PhysicsQueryFilter::new()).is_empty());
+
+    let projection = physics.project_point(Vec2::new(-50.0, 30.0), PhysicsQueryFilter::new()).unwrap();
+    assert_eq!(projection.entity, floor);
+    assert!(!projection.is_inside);
+    assert!((projection.point - Vec2::new(-50.0, 10.0)).length() < 1e-3);
+
+    let mut in_box = physics.entities_in_aabb(Vec2::new(40.0, 0.0), Vec2::new(60.0, 30.0), PhysicsQueryFilter::new());
+    in_box.sort();
+    let mut expected = vec![floor, block];
+    expected.sort();
+    assert_eq!(in_box, expected);
+
+    let touching = physics.entities_intersecting_shape(
+        &ColliderShape::Ball { radius: 3.0 },
+        Vec2::new(50.0, 22.0),
+        0.0,
+        PhysicsQueryFilter::new(),
+    );
+    assert_eq!(touching, vec![block]);
+}
+
+fn spawn_static_box(world: &mut EcsWorld, center: Vec2, width: f32, height: f32) -> hecs::Entity {
+    let entity = world.create_sprite_entity("enemy", center, width, height);
+    world.add_collider(entity, width, height, false).unwrap();
+    entity
+}
+
+fn spawn_character(world: &mut EcsWorld, position: Vec2) -> hecs::Entity {
+    let entity = world.create_sprite_entity("player", position, 20.0, 20.0);
+    world.add_collider(entity, 20.0, 20.0, false).unwrap();
+    world.add_character_controller(entity, CharacterController::default()).unwrap();
+    entity
+}
+
+fn set_character_velocity(world: &mut EcsWorld, entity: hecs::Entity, velocity: Vec2) {
+    world.world.get::<&mut CharacterController>(entity).unwrap().velocity = velocity;
+}
+
+fn character(world: &EcsWorld, entity: hecs::Entity) -> CharacterController {
+    *world.world.get::<&CharacterController>(entity).unwrap()
+}
+
+#[test]
+fn character_lands_on_ground_and_stops_at_walls() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    spawn_static_box(&mut world, Vec2::ZERO, 400.0, 20.0);
+    spawn_static_box(&mut world, Vec2::new(50.0, 50.0), 20.0, 100.0);
+    let player = spawn_character(&mut world, Vec2::new(0.0, 40.0));
+
+    set_character_velocity(&mut world, player, Vec2::new(0.0, -100.0));
+    step(&mut physics, &mut world, 60);
+    assert!(character(&world, player).grounded);
+    assert!((position(&world, player).y - 20.0).abs() < 1.0, "y = {}", position(&world, player).y);
+
+    set_character_velocity(&mut world, player, Vec2::new(200.0, -100.0));
+    step(&mut physics, &mut world, 60);
+    let controller = character(&world, player);
+    assert!(controller.touching_wall);
+    assert!(controller.grounded);
+    assert!(position(&world, player).x <= 30.0, "x = {}", position(&world, player).x);
+}
+
+#[test]
+fn character_climbs_low_steps_but_not_high_ones() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    spawn_static_box(&mut world, Vec2::ZERO, 400.0, 20.0);
+    // A 6 pixel step starting at x = 40, then a 30 pixel wall starting at x = 120.
+    spawn_static_box(&mut world, Vec2::new(80.0, 13.0), 80.0, 6.0);
+    spawn_static_box(&mut world, Vec2::new(140.0, 25.0), 40.0, 30.0);
+    let player = spawn_character(&mut world, Vec2::new(0.0, 21.0));
+
+    set_character_velocity(&mut world, player, Vec2::new(120.0, -50.0));
+    step(&mut physics, &mut world, 120);
+
+    let position = position(&world, player);
+    assert!(position.x > 40.0 && position.x <= 110.0, "x = {}", position.x);
+    assert!((position.y - 26.0).abs() < 1.0, "y = {}", position.y);
+    assert!(character(&world, player).touching_wall);
+}
+
+#[test]
+fn character_jumps_through_one_way_platforms_and_lands_on_them() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    spawn_static_box(&mut world, Vec2::ZERO, 400.0, 20.0);
+    let platform = spawn_static_box(&mut world, Vec2::new(0.0, 60.0), 100.0, 10.0);
+    world.world.insert_one(platform, OneWayPlatform).unwrap();
+    let player = spawn_character(&mut world, Vec2::new(0.0, 21.0));
+
+    set_character_velocity(&mut world, player, Vec2::new(0.0, 200.0));
+    step(&mut physics, &mut world, 30);
+    assert!(position(&world, player).y > 90.0, "y = {}", position(&world, player).y);
+    assert!(!character(&world, player).touching_ceiling);
+
+    set_character_velocity(&mut world, player, Vec2::new(0.0, -100.0));
+    step(&mut physics, &mut world, 60);
+    assert!(character(&world, player).grounded);
+    assert!((position(&world, player).y - 75.0).abs() < 1.0, "y = {}", position(&world, player).y);
+}
+
+#[test]
+fn character_reports_ceiling_contacts() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    spawn_static_box(&mut world, Vec2::new(0.0, 60.0), 100.0, 10.0);
+    let player = spawn_character(&mut world, Vec2::ZERO);
+
+    set_character_velocity(&mut world, player, Vec2::new(0.0, 200.0));
+    step(&mut physics, &mut world, 30);
+
+    assert!(character(&world, player).touching_ceiling);
+    assert!(!character(&world, player).grounded);
+    assert!(position(&world, player).y <= 45.0, "y = {}", position(&world, player).y);
+}
+
+fn spawn_body(world: &mut EcsWorld, position: Vec2) -> hecs::Entity {
+    let entity = world.create_sprite_entity("player", position, 10.0, 10.0);
+    world.add_rigid_body(entity, Vec2::ZERO, 1.0).unwrap();
+    world.add_collider(entity, 10.0, 10.0, false).unwrap();
+    entity
+}
+
+#[test]
+fn revolute_joint_to_the_world_swings_like_a_pendulum() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let bob = spawn_body(&mut world, Vec2::new(50.0, 0.0));
+    let mut joint = Joint::new(None, JointKind::Revolute);
+    joint.anchor = Vec2::new(-50.0, 0.0);
+    world.add_joint(bob, joint).unwrap();
+
+    step(&mut physics, &mut world, 300);
+
+    assert!(physics.impulse_joint_handle(bob).is_some());
+    let position = position(&world, bob);
+    assert!((position.length() - 50.0).abs() < 0.5, "distance = {}", position.length());
+    assert!(position.y < -5.0, "the bob should swing down, y = {}", position.y);
+}
+
+#[test]
+fn multibody_chains_persist_across_steps() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let mut links = Vec::new();
+    let mut parent = None;
+    for i in 0..4 {
+        let link = spawn_body(&mut world, Vec2::new(20.0 * (i + 1) as f32, 0.0));
+        let mut joint = Joint::new(parent, JointKind::Revolute);
+        joint.anchor = Vec2::new(-10.0, 0.0);
+        joint.target_anchor = Vec2::new(10.0, 0.0);
+        joint.multibody = true;
+        world.add_joint(link, joint).unwrap();
+        links.push(link);
+        parent = Some(link);
+    }
+
+    step(&mut physics, &mut world, 1);
+    let handles: Vec<_> = links.iter().map(|link| physics.multibody_joint_handle(*link)).collect();
+    assert!(handles.iter().all(Option::is_some));
+
+    step(&mut physics, &mut world, 300);
+    let after: Vec<_> = links.iter().map(|link| physics.multibody_joint_handle(*link)).collect();
+    assert_eq!(handles, after);
+    let anchor_at = |entity, local: Vec2| {
+        let transform = *world.world.get::<&Transform>(entity).unwrap();
+        transform.position + Vec2::from_angle(transform.rotation).rotate(local)
+    };
+    for pair in links.windows(2) {
+        let gap = anchor_at(pair[0], Vec2::new(10.0, 0.0)).distance(anchor_at(pair[1], Vec2::new(-10.0, 0.0)));
+        assert!(gap < 0.1, "chain stretched by {}", gap);
+    }
+}
+
+#[test]
+fn rope_joints_limit_distance() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let anchor = spawn_body(&mut world, Vec2::ZERO);
+    world.add_joint(anchor, Joint::new(None, JointKind::Fixed)).unwrap();
+    let weight = spawn_body(&mut world, Vec2::new(10.0, 0.0));
+    world.world.get::<&mut RigidBody>(weight).unwrap().velocity = Vec2::new(100.0, 0.0);
+    world.add_joint(weight, Joint::new(Some(anchor), JointKind::Rope { max_length: 40.0 })).unwrap();
+
+    for _ in 0..120 {
+        step(&mut physics, &mut world, 1);
+        let distance = position(&world, weight).distance(position(&world, anchor));
+        assert!(distance < 40.5, "rope stretched to {}", distance);
+    }
+    assert!(position(&world, anchor).length() < 0.1);
+}
+
+#[test]
+fn prismatic_motor_slides_until_its_limit() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let slider = spawn_body(&mut world, Vec2::ZERO);
+    let mut joint = Joint::new(None, JointKind::Prismatic { axis: Vec2::X });
+    joint.limits = Some([0.0, 20.0]);
+    joint.motor = Some(JointMotor::velocity(30.0, 1000.0));
+    world.add_joint(slider, joint).unwrap();
+
+    step(&mut physics, &mut world, 120);
+
+    let position = position(&world, slider);
+    assert!((position.x - 20.0).abs() < 0.5, "x = {}", position.x);
+    assert!(position.y.abs() < 0.1, "y = {}", position.y);
+
+    world.world.remove_one::<Joint>(slider).unwrap();
+    step(&mut physics, &mut world, 1);
+    assert!(physics.impulse_joint_handle(slider).is_none());
+}
+
+#[test]
+fn gravity_is_scaled_by_pixels_per_meter() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::with_config(PhysicsConfig {
+        pixels_per_meter: 50.0,
+        gravity: Vec2::new(0.0, -10.0),
+        ..PhysicsConfig::default()
+    })
+    .unwrap();
+
+    let body = spawn_body(&mut world, Vec2::ZERO);
+    step(&mut physics, &mut world, 60);
+
+    // Free fall for one second: half of 10 m/s² times 50 px/m.
+    let fallen = -position(&world, body).y;
+    assert!((fallen - 250.0).abs() < 10.0, "fell {} pixels", fallen);
+    let velocity = world.world.get::<&RigidBody>(body).unwrap().velocity;
+    assert!((velocity.y + 500.0).abs() < 1.0, "velocity = {:?}", velocity);
+}
+
+#[test]
+fn config_changes_apply_at_runtime() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let body = spawn_body(&mut world, Vec2::ZERO);
+    step(&mut physics, &mut world, 30);
+    let before = position(&world, body);
+
+    physics.set_config(PhysicsConfig {
+        pixels_per_meter: 32.0,
+        gravity: Vec2::ZERO,
+        ..*physics.config()
+    })
+    .unwrap();
+    let velocity = world.world.get::<&RigidBody>(body).unwrap().velocity;
+    step(&mut physics, &mut world, 30);
+
+    // The body is rebuilt at the same pixel position and keeps drifting at the same
+    // pixel velocity, now without accelerating.
+    let expected = before + velocity * 30.0 * DT;
+    assert!(position(&world, body).distance(expected) < 0.5, "{:?} != {:?}", position(&world, body), expected);
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let mut physics = PhysicsSystem::new();
+    for invalid in [
+        PhysicsConfig { pixels_per_meter: 0.0, ..PhysicsConfig::default() },
+        PhysicsConfig { pixels_per_meter: -10.0, ..PhysicsConfig::default() },
+        PhysicsConfig { timestep: 0.0, ..PhysicsConfig::default() },
+        PhysicsConfig { timestep: -1.0 / 60.0, ..PhysicsConfig::default() },
+        PhysicsConfig { timestep: f32::NAN, ..PhysicsConfig::default() },
+    ] {
+        assert!(invalid.validate().is_err(), "{:?}", invalid);
+        assert!(PhysicsSystem::with_config(invalid).is_err());
+        assert!(physics.set_config(invalid).is_err());
+    }
+    assert_eq!(*physics.config(), PhysicsConfig::default());
+}
+
+#[test]
+fn collision_layers_are_limited_to_32() {
+    let mut layers = CollisionLayers::new();
+    let player = layers.add("player").unwrap();
+    assert_eq!(layers.add("player").unwrap(), player);
+    for i in 1..32 {
+        layers.add(&format!("layer {}", i)).unwrap();
+    }
+    assert!(layers.add("one too many").is_err());
+    assert_eq!(layers.name(player), Some("player"));
+    assert!(layers.filter(&["player"], &["missing"]).is_err());
+}
+
+#[test]
+fn collision_layers_filter_contacts_and_queries() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::with_config(PhysicsConfig {
+        gravity: Vec2::ZERO,
+        ..PhysicsConfig::default()
+    })
+    .unwrap();
+    let layers = physics.collision_layers_mut();
+    layers.add("player").unwrap();
+    layers.add("enemy").unwrap();
+    layers.add("player_bullet").unwrap();
+    let player_filter = layers.filter(&["player"], &["enemy"]).unwrap();
+    let enemy_filter = layers.filter(&["enemy"], &["player", "player_bullet"]).unwrap();
+    let bullet_filter = layers.filter(&["player_bullet"], &["enemy"]).unwrap();
+
+    let player = spawn_static_box(&mut world, Vec2::new(40.0, 0.0), 20.0, 20.0);
+    world.set_collision_filter(player, player_filter).unwrap();
+    let enemy = spawn_static_box(&mut world, Vec2::new(100.0, 0.0), 20.0, 20.0);
+    world.set_collision_filter(enemy, enemy_filter).unwrap();
+    let bullet = spawn_body(&mut world, Vec2::ZERO);
+    world.set_collision_filter(bullet, bullet_filter).unwrap();
+    world.world.get::<&mut RigidBody>(bullet).unwrap().velocity = Vec2::new(120.0, 0.0);
+
+    step(&mut physics, &mut world, 60);
+
+    // The bullet flies through the player and stops at the enemy.
+    let events: Vec<PhysicsEvent> = physics.drain_events().collect();
+    assert_eq!(events, vec![PhysicsEvent::CollisionStarted { a: enemy, b: bullet }]);
+    assert!(position(&world, bullet).x < 86.0, "x = {}", position(&world, bullet).x);
+
+    let hits = physics.cast_ray_all(
+        Vec2::new(0.0, 50.0),
+        Vec2::new(1.0, -1.0),
+        200.0,
+        PhysicsQueryFilter::new().collision_filter(bullet_filter),
+    );
+    assert!(hits.iter().all(|hit| hit.entity != player));
+    let at_player = physics.entities_at_point(
+        Vec2::new(40.0, 0.0),
+        PhysicsQueryFilter::new().collision_filter(enemy_filter),
+    );
+    assert_eq!(at_player, vec![player]);
+    let ignored = physics.entities_at_point(
+        Vec2::new(40.0, 0.0),
+        PhysicsQueryFilter::new().collision_filter(bullet_filter),
+    );
+    assert!(ignored.is_empty());
+}
+
+#[test]
+fn pair_filter_lets_chosen_entities_pass_through() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    spawn_static_box(&mut world, Vec2::ZERO, 200.0, 20.0);
+    let ghost = spawn_body(&mut world, Vec2::new(-20.0, 20.0));
+    let solid = spawn_body(&mut world, Vec2::new(20.0, 20.0));
+    physics.set_pair_filter(move |a, b| a != ghost && b != ghost);
+
+    step(&mut physics, &mut world, 60);
+
+    assert!(position(&world, ghost).y < -20.0, "y = {}", position(&world, ghost).y);
+    assert!((position(&world, solid).y - 15.0).abs() < 0.5, "y = {}", position(&world, solid).y);
+}
+
+#[test]
+fn dynamic_bodies_pass_up_through_one_way_platforms() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    let platform = spawn_static_box(&mut world, Vec2::new(0.0, 100.0), 100.0, 10.0);
+    world.world.insert_one(platform, OneWayPlatform).unwrap();
+    let ball = spawn_body(&mut world, Vec2::ZERO);
+    world.world.get::<&mut RigidBody>(ball).unwrap().velocity = Vec2::new(0.0, 600.0);
+
+    step(&mut physics, &mut world, 180);
+
+    // The ball rises through the platform, falls back and lands on top of it.
+    assert!((position(&world, ball).y - 110.0).abs() < 1.0, "y = {}", position(&world, ball).y);
+}
+
+#[test]
+fn debug_lines_outline_colliders_in_pixels_coloured_by_body_type() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+    spawn_static_box(&mut world, Vec2::new(0.0, -100.0), 200.0, 20.0);
+    spawn_body(&mut world, Vec2::new(0.0, 50.0));
+    let trigger = world.create_sprite_entity("player", Vec2::new(150.0, 0.0), 30.0, 30.0);
+    world.add_collider(trigger, 30.0, 30.0, true).unwrap();
+    step(&mut physics, &mut world, 1);
+
+    let lines = physics.debug_lines();
+    let colors_near = |point: Vec2| {
+        lines
+            .iter()
+            .filter(|line| (line.start - point).length() < 1.0 || (line.end - point).length() < 1.0)
+            .map(|line| line.color)
+            .collect::<Vec<_>>()
+    };
+
+    let ground_corner = colors_near(Vec2::new(100.0, -90.0));
+    let trigger_corner = colors_near(Vec2::new(165.0, 15.0));
+    assert!(!ground_corner.is_empty(), "ground outline should be drawn in pixels");
+    assert!(!trigger_corner.is_empty(), "trigger outline should be drawn in pixels");
+    assert!(
+        trigger_corner.iter().any(|color| !ground_corner.contains(color)),
+        "triggers and static colliders should use different colours"
+    );
+}
+
+/// Bit patterns of every entity's position, rotation and velocity, in entity order.
+fn simulation_fingerprint(world: &EcsWorld) -> Vec<u32> {
+    let mut entities: Vec<_> = world.world.iter().map(|entity| entity.entity()).collect();
+    entities.sort_by_key(|entity| entity.to_bits());
+    let mut bits = Vec::new();
+    for entity in entities {
+        let transform = *world.world.get::<&Transform>(entity).unwrap();
+        bits.extend([transform.position.x, transform.position.y, transform.rotation].map(f32::to_bits));
+        if let Ok(body) = world.world.get::<&RigidBody>(entity) {
+            bits.extend([body.velocity.x, body.velocity.y].map(f32::to_bits));
+        }
+    }
+    bits
+}
+
+#[test]
+fn restoring_a_snapshot_replays_the_simulation_bit_for_bit() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+    spawn_static_box(&mut world, Vec2::new(0.0, -100.0), 400.0, 20.0);
+    for i in 0..5 {
+        let body = spawn_body(&mut world, Vec2::new(i as f32 * 3.0, i as f32 * 12.0));
+        world.world.get::<&mut RigidBody>(body).unwrap().velocity = Vec2::new(20.0, 0.0);
+    }
+    let anchor = spawn_body(&mut world, Vec2::new(-100.0, 50.0));
+    let bob = spawn_body(&mut world, Vec2::new(-60.0, 50.0));
+    world.add_joint(anchor, Joint::new(None, JointKind::Revolute)).unwrap();
+    world.add_joint(bob, Joint { anchor: Vec2::new(-40.0, 0.0), ..Joint::new(Some(anchor), JointKind::Revolute) }).unwrap();
+    let player = spawn_character(&mut world, Vec2::new(120.0, -70.0));
+
+    let run = |physics: &mut PhysicsSystem, world: &mut EcsWorld| {
+        let mut history = Vec::new();
+        for frame in 0..60 {
+            set_character_velocity(world, player, Vec2::new(if frame < 30 { 60.0 } else { -60.0 }, -100.0));
+            step(physics, world, 1);
+            history.push(simulation_fingerprint(world));
+        }
+        history
+    };
+
+    step(&mut physics, &mut world, 20);
+    let snapshot = physics.snapshot(&world.world).unwrap();
+    let original = run(&mut physics, &mut world);
+
+    physics.restore(&mut world.world, &snapshot).unwrap();
+    assert_eq!(run(&mut physics, &mut world), original, "rollback should replay identically");
+
+    let mut fresh = PhysicsSystem::new();
+    fresh.restore(&mut world.world, &snapshot).unwrap();
+    assert_eq!(run(&mut fresh, &mut world), original, "a new system restored from bytes should match");
+
+    assert!(physics.restore(&mut world.world, &snapshot[..snapshot.len() / 2]).is_err());
+}
+
+fn rigid_body(world: &mut EcsWorld, entity: hecs::Entity) -> hecs::RefMut<'_, RigidBody> {
+    world.world.get::<&mut RigidBody>(entity).unwrap()
+}
+
+#[test]
+fn friction_decides_how_far_a_box_slides() {
+    let slide = |friction: f32| {
+        let mut world = EcsWorld::new();
+        let mut physics = PhysicsSystem::new();
+        let floor = spawn_static_box(&mut world, Vec2::new(0.0, -20.0), 2000.0, 20.0);
+        let material = PhysicsMaterial { friction, friction_combine: CombineRule::Min, ..Default::default() };
+        world.set_physics_material(floor, material).unwrap();
+        let crate_box = spawn_body(&mut world, Vec2::new(0.0, -5.0));
+        world.set_physics_material(crate_box, material).unwrap();
+        rigid_body(&mut world, crate_box).velocity = Vec2::new(300.0, 0.0);
+        step(&mut physics, &mut world, 60);
+        position(&world, crate_box).x
+    };
+
+    let on_ice = slide(0.0);
+    let on_rubber = slide(1.0);
+    assert!(on_ice > 250.0, "a frictionless box keeps sliding, x = {}", on_ice);
+    assert!(on_rubber < on_ice / 2.0, "friction should stop the box, x = {}", on_rubber);
+}
+
+#[test]
+fn restitution_makes_a_ball_bounce() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+    spawn_static_box(&mut world, Vec2::new(0.0, -20.0), 400.0, 20.0);
+    let ball = world.create_sprite_entity("player", Vec2::new(0.0, 100.0), 10.0, 10.0);
+    world.add_rigid_body(ball, Vec2::ZERO, 1.0).unwrap();
+    world.add_collider_shape(ball, ColliderShape::Ball { radius: 5.0 }, false).unwrap();
+    world.set_physics_material(
+        ball,
+        PhysicsMaterial { restitution: 1.0, restitution_combine: CombineRule::Max, ..Default::default() },
+    ).unwrap();
+
+    let mut bounced = false;
+    for _ in 0..120 {
+        step(&mut physics, &mut world, 1);
+        bounced |= rigid_body(&mut world, ball).velocity.y > 100.0;
+    }
+    assert!(bounced, "the ball should rebound off the floor");
+}
+
+#[test]
+fn body_type_gravity_scale_and_locked_rotation_are_applied() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+    let wall = spawn_body(&mut world, Vec2::new(0.0, 0.0));
+    rigid_body(&mut world, wall).body_type = BodyType::Static;
+    let floating = spawn_body(&mut world, Vec2::new(100.0, 0.0));
+    rigid_body(&mut world, floating).gravity_scale = 0.0;
+    let platform = spawn_body(&mut world, Vec2::new(200.0, 0.0));
+    rigid_body(&mut world, platform).body_type = BodyType::Kinematic;
+    rigid_body(&mut world, platform).velocity = Vec2::new(60.0, 0.0);
+    let upright = spawn_body(&mut world, Vec2::new(300.0, 0.0));
+    rigid_body(&mut world, upright).lock_rotation = true;
+    world.add_joint(
+        upright,
+        Joint {
+            anchor: Vec2::new(20.0, 0.0),
+            target_anchor: Vec2::new(320.0, 0.0),
+            ..Joint::new(None, JointKind::Revolute)
+        },
+    ).unwrap();
+    step(&mut physics, &mut world, 60);
+
+    assert_eq!(position(&world, wall), Vec2::ZERO);
+    assert_eq!(position(&world, floating), Vec2::new(100.0, 0.0));
+    let platform_position = position(&world, platform);
+    assert!((platform_position - Vec2::new(260.0, 0.0)).length() < 1.0, "{:?}", platform_position);
+    let rotation = world.world.get::<&Transform>(upright).unwrap().rotation;
+    assert!(rotation.abs() < 1e-4, "a locked body shouldn't swing, rotation = {}", rotation);
+
+    // Switching a body to dynamic at runtime lets it fall.
+    rigid_body(&mut world, wall).body_type = BodyType::Dynamic;
+    step(&mut physics, &mut world, 30);
+    assert!(position(&world, wall).y < -50.0);
+}
+
+#[test]
+fn linear_damping_slows_a_body_down() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::with_config(PhysicsConfig { gravity: Vec2::ZERO, ..Default::default() }).unwrap();
+    let body = spawn_body(&mut world, Vec2::ZERO);
+    rigid_body(&mut world, body).velocity = Vec2::new(100.0, 0.0);
+    rigid_body(&mut world, body).linear_damping = 2.0;
+    step(&mut physics, &mut world, 60);
+
+    let speed = rigid_body(&mut world, body).velocity.x;
+    assert!(speed > 5.0 && speed < 30.0, "speed = {}", speed);
+}
+
+fn weightless() -> PhysicsSystem {
+    PhysicsSystem::with_config(PhysicsConfig { gravity: Vec2::ZERO, ..Default::default() }).unwrap()
+}
+
+#[test]
+fn impulses_change_velocity_once() {
+    let mut world = EcsWorld::new();
+    let mut physics = weightless();
+    let body = spawn_body(&mut world, Vec2::ZERO);
+    rigid_body(&mut world, body).mass = 2.0;
+    world.apply_impulse(body, Vec2::new(100.0, 0.0)).unwrap();
+    world.apply_impulse(body, Vec2::new(100.0, 0.0)).unwrap();
+    step(&mut physics, &mut world, 1);
+    let velocity = world.velocity(body).unwrap();
+    assert!((velocity - Vec2::new(100.0, 0.0)).length() < 1e-2, "{:?}", velocity);
+
+    step(&mut physics, &mut world, 30);
+    let velocity = world.velocity(body).unwrap();
+    assert!((velocity - Vec2::new(100.0, 0.0)).length() < 1e-2, "impulses are applied once, {:?}", velocity);
+}
+
+#[test]
+fn mass_edits_apply_to_bodies_without_a_collider() {
+    let mut world = EcsWorld::new();
+    let mut physics = weightless();
+    let body = world.create_sprite_entity("player", Vec2::ZERO, 10.0, 10.0);
+    world.add_rigid_body(body, Vec2::ZERO, 1.0).unwrap();
+    step(&mut physics, &mut world, 1);
+
+    rigid_body(&mut world, body).mass = 4.0;
+    step(&mut physics, &mut world, 1);
+    world.apply_impulse(body, Vec2::new(100.0, 0.0)).unwrap();
+    step(&mut physics, &mut world, 1);
+    let velocity = world.velocity(body).unwrap();
+    assert!((velocity - Vec2::new(25.0, 0.0)).length() < 1e-2, "{:?}", velocity);
+}
+
+#[test]
+fn impulses_without_a_body_are_dropped() {
+    let mut world = EcsWorld::new();
+    let mut physics = weightless();
+    let wall = world.create_sprite_entity("enemy", Vec2::ZERO, 10.0, 10.0);
+    world.add_collider(wall, 10.0, 10.0, false).unwrap();
+    let marker = world.create_entity();
+    world.apply_impulse(wall, Vec2::new(100.0, 0.0)).unwrap();
+    world.apply_impulse_at_point(marker, Vec2::X, Vec2::ZERO).unwrap();
+    step(&mut physics, &mut world, 1);
+
+    for entity in [wall, marker] {
+        assert!(world.world.get::<&ExternalImpulse>(entity).unwrap().is_empty());
+    }
+}
+
+#[test]
+fn impulse_off_centre_and_torque_impulse_spin_a_body() {
+    let mut world = EcsWorld::new();
+    let mut physics = weightless();
+    let pushed = spawn_body(&mut world, Vec2::ZERO);
+    world.apply_impulse_at_point(pushed, Vec2::new(0.0, 10.0), Vec2::new(5.0, 0.0)).unwrap();
+    let twisted = spawn_body(&mut world, Vec2::new(100.0, 0.0));
+    world.apply_torque_impulse(twisted, -100.0).unwrap();
+    step(&mut physics, &mut world, 1);
+
+    assert!(world.angular_velocity(pushed).unwrap() > 0.1, "pushing the right side up spins counter-clockwise");
+    assert!(world.velocity(pushed).unwrap().y > 0.0);
+    assert!(world.angular_velocity(twisted).unwrap() < -0.1);
+}
+
+#[test]
+fn forces_and_acceleration_act_every_step() {
+    let mut world = EcsWorld::new();
+    let mut physics = weightless();
+    let pushed = spawn_body(&mut world, Vec2::ZERO);
+    rigid_body(&mut world, pushed).mass = 2.0;
+    world.set_external_force(pushed, Vec2::new(200.0, 0.0), 0.0).unwrap();
+    let accelerated = spawn_body(&mut world, Vec2::new(0.0, 100.0));
+    rigid_body(&mut world, accelerated).acceleration = Vec2::new(0.0, 50.0);
+    step(&mut physics, &mut world, 60);
+
+    let pushed_velocity = world.velocity(pushed).unwrap();
+    assert!((pushed_velocity.x - 100.0).abs() < 1.0, "{:?}", pushed_velocity);
+    let accelerated_velocity = world.velocity(accelerated).unwrap();
+    assert!((accelerated_velocity.y - 50.0).abs() < 1.0, "{:?}", accelerated_velocity);
+
+    world.world.insert_one(pushed, ExternalForce::default()).unwrap();
+    step(&mut physics, &mut world, 30);
+    assert!((world.velocity(pushed).unwrap().x - pushed_velocity.x).abs() < 1e-2, "removing the force stops acceleration");
+}
+
+#[test]
+fn angular_velocity_round_trips_through_the_simulation() {
+    let mut world = EcsWorld::new();
+    let mut physics = weightless();
+    let body = spawn_body(&mut world, Vec2::ZERO);
+    world.set_angular_velocity(body, 2.0).unwrap();
+    step(&mut physics, &mut world, 30);
+
+    assert!((world.angular_velocity(body).unwrap() - 2.0).abs() < 1e-4);
+    let rotation = world.world.get::<&Transform>(body).unwrap().rotation;
+    assert!((rotation - 1.0).abs() < 1e-2, "rotation = {}", rotation);
+}
@@ -0,0 +1,140 @@
+use engine_2d::demo_scene::{load_demo_scene, register_demo_prefabs};
+use engine_2d::ecs::{Collider, EcsWorld, RigidBody, Sprite, Transform};
+use engine_2d::prefab::{Overrides, Prefab};
+use glam::{Vec2, Vec4};
+use serde_json::json;
+
+fn transform(position: Vec2) -> Transform {
+    Transform { position, rotation: 0.0, scale: Vec2::ONE }
+}
+
+#[test]
+fn demo_enemy_prefab_matches_the_hand_built_enemy() {
+    let mut ecs = EcsWorld::new();
+    register_demo_prefabs(&mut ecs);
+    let position = Vec2::new(-150.0, 200.0);
+    let from_prefab = ecs.spawn_prefab("enemy", Overrides::new().with("Transform", &transform(position))).unwrap();
+    let by_hand = ecs.create_sprite_entity("enemy", position, 80.0, 80.0);
+    ecs.add_rigid_body(by_hand, Vec2::new(0.0, -30.0), 1.0).unwrap();
+    ecs.add_collider(by_hand, 80.0, 80.0, false).unwrap();
+
+    assert_eq!(*ecs.world.get::<&Transform>(from_prefab).unwrap(), *ecs.world.get::<&Transform>(by_hand).unwrap());
+    assert_eq!(*ecs.world.get::<&Sprite>(from_prefab).unwrap(), *ecs.world.get::<&Sprite>(by_hand).unwrap());
+    assert_eq!(*ecs.world.get::<&RigidBody>(from_prefab).unwrap(), *ecs.world.get::<&RigidBody>(by_hand).unwrap());
+    assert_eq!(*ecs.world.get::<&Collider>(from_prefab).unwrap(), *ecs.world.get::<&Collider>(by_hand).unwrap());
+}
+
+#[test]
+fn loading_the_demo_scene_keeps_a_registered_enemy_prefab() {
+    let mut ecs = EcsWorld::new();
+    let sprite = Sprite {
+        texture_name: "player".to_string(),
+        width: 32.0,
+        height: 32.0,
+        uv_rect: Vec4::new(0.0, 0.0, 1.0, 1.0),
+        tint: Vec4::ONE,
+    };
+    ecs.register_prefab("enemy", Prefab::new().with("Transform", &transform(Vec2::ZERO)).with("Sprite", &sprite));
+    load_demo_scene(&mut ecs);
+
+    let enemy = ecs.spawn_prefab("enemy", Overrides::new()).unwrap();
+    assert_eq!(*ecs.world.get::<&Sprite>(enemy).unwrap(), sprite);
+    assert!(ecs.world.get::<&RigidBody>(enemy).is_err());
+}
+
+#[test]
+fn overrides_replace_or_patch_components_per_instance() {
+    let mut ecs = EcsWorld::new();
+    register_demo_prefabs(&mut ecs);
+    let red = ecs
+        .spawn_prefab(
+            "enemy",
+            Overrides::new()
+                .patch("Sprite", json!({ "tint": [1.0, 0.0, 0.0, 1.0] }))
+                .patch("RigidBody", json!({ "mass": 4.0 }))
+                .patch("Transform", json!({ "position": [5.0, 6.0] })),
+        )
+        .unwrap();
+    let plain = ecs.spawn_prefab("enemy", Overrides::new()).unwrap();
+
+    let sprite = ecs.world.get::<&Sprite>(red).unwrap();
+    assert_eq!(sprite.tint, Vec4::new(1.0, 0.0, 0.0, 1.0));
+    assert_eq!(sprite.width, 80.0);
+    let body = ecs.world.get::<&RigidBody>(red).unwrap();
+    assert_eq!((body.mass, body.velocity), (4.0, Vec2::new(0.0, -30.0)));
+    assert_eq!(ecs.world.get::<&Transform>(red).unwrap().position, Vec2::new(5.0, 6.0));
+    assert_eq!(ecs.world.get::<&Sprite>(plain).unwrap().tint, Vec4::ONE);
+}
+
+#[test]
+fn prefabs_extend_bases_and_nest_children() {
+    let mut ecs = EcsWorld::new();
+    register_demo_prefabs(&mut ecs);
+    ecs.register_prefab("muzzle", Prefab::new().with("Transform", &transform(Vec2::new(0.0, 4.0))));
+    ecs.register_prefab(
+        "gun",
+        Prefab::new()
+            .with("Transform", &transform(Vec2::new(10.0, 0.0)))
+            .child("muzzle", Overrides::new()),
+    );
+    ecs.register_prefab(
+        "armed_enemy",
+        Prefab::extending("enemy")
+            .with("RigidBody", &RigidBody::new(Vec2::ZERO, 2.0))
+            .child("gun", Overrides::new().patch("Transform", json!({ "rotation": 1.0 }))),
+    );
+
+    let enemy = ecs.spawn_prefab("armed_enemy", Overrides::new()).unwrap();
+    assert_eq!(ecs.world.get::<&Sprite>(enemy).unwrap().texture_name, "enemy");
+    assert_eq!(ecs.world.get::<&RigidBody>(enemy).unwrap().mass, 2.0);
+
+    let guns = ecs.children(enemy);
+    assert_eq!(guns.len(), 1);
+    let gun = *ecs.world.get::<&Transform>(guns[0]).unwrap();
+    assert_eq!((gun.position, gun.rotation), (Vec2::new(10.0, 0.0), 1.0));
+    let muzzles = ecs.children(guns[0]);
+    assert_eq!(muzzles.len(), 1);
+    assert_eq!(ecs.world.get::<&Transform>(muzzles[0]).unwrap().position, Vec2::new(0.0, 4.0));
+
+    ecs.despawn_recursive(enemy).unwrap();
+    assert!(ecs.world.is_empty());
+}
+
+#[test]
+fn broken_prefabs_spawn_nothing() {
+    let mut ecs = EcsWorld::new();
+    ecs.register_prefab("loop", Prefab::new().child("loop", Overrides::new()));
+    ecs.register_prefab("a", Prefab::extending("b"));
+    ecs.register_prefab("b", Prefab::extending("a"));
+    ecs.register_prefab("bad", Prefab::new().with("Transform", &transform(Vec2::ZERO)).with("Sprite", &1));
+
+    assert!(ecs.spawn_prefab("missing", Overrides::new()).unwrap_err().contains("Unknown prefab"));
+    assert!(ecs.spawn_prefab("loop", Overrides::new()).unwrap_err().contains("contains itself"));
+    assert!(ecs.spawn_prefab("a", Overrides::new()).unwrap_err().contains("its own base"));
+    assert!(ecs.spawn_prefab("bad", Overrides::new()).unwrap_err().contains("Sprite"));
+    assert!(ecs.world.is_empty());
+}
+
+#[test]
+fn prefabs_load_from_ron() {
+    let path = std::env::temp_dir().join(format!("engine_2d_{}_crate.ron", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"(
+            base: Some("enemy"),
+            components: {
+                "Transform": { "position": [1.0, 2.0], "rotation": 0.0, "scale": [2.0, 2.0] },
+            },
+        )"#,
+    )
+    .unwrap();
+    let prefab = Prefab::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let mut ecs = EcsWorld::new();
+    register_demo_prefabs(&mut ecs);
+    ecs.register_prefab("big_enemy", prefab);
+    let enemy = ecs.spawn_prefab("big_enemy", Overrides::new()).unwrap();
+    assert_eq!(ecs.world.get::<&Transform>(enemy).unwrap().scale, Vec2::splat(2.0));
+    assert!(ecs.world.get::<&Collider>(enemy).is_ok());
+}
@@ -0,0 +1,108 @@
+use engine_2d::demo_scene::load_demo_scene;
+use engine_2d::ecs::{Collider, EcsWorld, GlobalTransform, RigidBody, Sprite, Transform};
+use engine_2d::scene::{ComponentRegistry, Scene, SceneFormat};
+use glam::Vec2;
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Health {
+    current: u32,
+    max: u32,
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("engine_2d_{}_{}", std::process::id(), name))
+}
+
+type Saved = (Option<Transform>, Option<Sprite>, Option<RigidBody>, Option<Collider>);
+
+/// Entities in spawn order with the components scenes cover.
+fn snapshot(ecs: &EcsWorld) -> Vec<Saved> {
+    let mut entities: Vec<Entity> = ecs.world.iter().map(|entity_ref| entity_ref.entity()).collect();
+    entities.sort_by_key(|entity| entity.id());
+    entities
+        .into_iter()
+        .map(|entity| {
+            (
+                ecs.world.get::<&Transform>(entity).ok().map(|transform| *transform),
+                ecs.world.get::<&Sprite>(entity).ok().map(|sprite| (*sprite).clone()),
+                ecs.world.get::<&RigidBody>(entity).ok().map(|body| *body),
+                ecs.world.get::<&Collider>(entity).ok().map(|collider| (*collider).clone()),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn demo_scene_round_trips_through_ron_and_json() {
+    let mut original = EcsWorld::new();
+    load_demo_scene(&mut original);
+    let expected = snapshot(&original);
+    assert_eq!(expected.len(), 7);
+
+    for name in ["demo.ron", "demo.json"] {
+        let path = temp_path(name);
+        original.save_scene(&path).unwrap();
+        let mut loaded = EcsWorld::new();
+        let entities = loaded.load_scene(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(entities.len(), 7);
+        assert_eq!(snapshot(&loaded), expected, "{name}");
+    }
+}
+
+#[test]
+fn registered_components_and_hierarchy_are_saved() {
+    let mut ecs = EcsWorld::new();
+    ecs.register_component::<Health>("Health");
+    let player = ecs.create_sprite_entity("player", Vec2::new(10.0, 0.0), 10.0, 10.0);
+    ecs.world.insert_one(player, Health { current: 3, max: 5 }).unwrap();
+    let gun = ecs.create_sprite_entity("player", Vec2::new(0.0, 0.1), 2.0, 2.0);
+    ecs.set_parent(gun, player).unwrap();
+    // Components that aren't registered are left out.
+    ecs.world.spawn((42u32,));
+
+    let path = temp_path("hierarchy.ron");
+    ecs.save_scene(&path).unwrap();
+    let mut loaded = EcsWorld::new();
+    loaded.register_component::<Health>("Health");
+    let entities = loaded.load_scene(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(entities.len(), 2);
+    assert_eq!(*loaded.world.get::<&Health>(entities[0]).unwrap(), Health { current: 3, max: 5 });
+    assert_eq!(loaded.parent(entities[1]), Some(entities[0]));
+    let global = *loaded.world.get::<&GlobalTransform>(entities[1]).unwrap();
+    assert!(global.translation().abs_diff_eq(Vec2::new(10.0, 0.1), 1e-6));
+}
+
+#[test]
+fn invalid_scenes_spawn_nothing() {
+    let mut ecs = EcsWorld::new();
+    ecs.register_component::<Health>("Health");
+    ecs.create_sprite_entity("player", Vec2::ZERO, 10.0, 10.0);
+    let health = ecs.create_sprite_entity("player", Vec2::ZERO, 10.0, 10.0);
+    ecs.world.insert_one(health, Health { current: 1, max: 1 }).unwrap();
+    let text = Scene::from_world(&ecs.world, &ecs.resource::<ComponentRegistry>().unwrap())
+        .unwrap()
+        .to_string(SceneFormat::Json)
+        .unwrap();
+
+    // A world that doesn't know about `Health`.
+    let mut loaded = EcsWorld::new();
+    let scene = Scene::from_str(&text, SceneFormat::Json).unwrap();
+    let error = scene
+        .spawn_into(&mut loaded.world, &loaded.resources.get::<ComponentRegistry>().unwrap())
+        .unwrap_err();
+    assert!(error.contains("Health"), "{error}");
+    assert!(loaded.world.is_empty());
+
+    assert!(loaded.load_scene(temp_path("scene.txt")).unwrap_err().contains(".ron or .json"));
+    // Parses fine, but isn't a `Transform`.
+    let bad_transform = Scene::from_str("(entities: [(components: {\"Transform\": 1})])", SceneFormat::Ron).unwrap();
+    assert!(bad_transform.spawn_into(&mut loaded.world, &loaded.resources.get::<ComponentRegistry>().unwrap()).is_err());
+    assert!(loaded.world.is_empty());
+}
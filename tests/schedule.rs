@@ -0,0 +1,185 @@
+use engine_2d::audio::AudioSystem;
+use engine_2d::ecs::{Resources, Transform};
+use engine_2d::engine::Engine;
+use engine_2d::input::InputManager;
+use engine_2d::physics::PhysicsSystem;
+use engine_2d::resources::ResourceManager;
+use engine_2d::schedule::{Schedule, Stage, System};
+use engine_2d::time::Time;
+use glam::Vec2;
+use hecs::World;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+/// System that appends its name to `log` every time it runs.
+fn logging(name: &str, log: &Log) -> System {
+    let log = log.clone();
+    let entry = name.to_string();
+    System::new(name, move |_world, _resources| log.borrow_mut().push(entry.clone()))
+}
+
+fn run(schedule: &mut Schedule, stage: Stage) {
+    schedule.run_stage(stage, &mut World::new(), &Resources::new());
+}
+
+#[test]
+fn systems_run_in_registration_order_unless_constrained() {
+    let log = Log::default();
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, logging("a", &log)).unwrap();
+    schedule.add_system(Stage::Update, logging("b", &log)).unwrap();
+    schedule.add_system(Stage::Update, logging("c", &log).before("a")).unwrap();
+    schedule.add_system(Stage::Update, logging("d", &log).after("e")).unwrap();
+    schedule.add_system(Stage::Update, logging("e", &log)).unwrap();
+
+    assert_eq!(schedule.system_names(Stage::Update), ["b", "c", "a", "e", "d"]);
+    run(&mut schedule, Stage::Update);
+    assert_eq!(*log.borrow(), ["b", "c", "a", "e", "d"]);
+
+    assert!(schedule.remove_system("a"));
+    assert!(!schedule.remove_system("a"));
+    assert_eq!(schedule.system_names(Stage::Update), ["b", "c", "e", "d"]);
+}
+
+#[test]
+fn ordering_cycles_and_duplicate_names_are_rejected() {
+    let log = Log::default();
+    let mut schedule = Schedule::new();
+    schedule.add_system(Stage::Update, logging("a", &log)).unwrap();
+    schedule.add_system(Stage::Update, logging("b", &log).after("a")).unwrap();
+
+    let error = schedule.add_system(Stage::Update, logging("c", &log).after("b").before("a")).unwrap_err();
+    assert!(error.contains("cycle"), "{error}");
+    assert!(!schedule.contains("c"));
+
+    let error = schedule.add_system(Stage::PostUpdate, logging("a", &log)).unwrap_err();
+    assert!(error.contains("already registered"), "{error}");
+    assert_eq!(schedule.stage_of("a"), Some(Stage::Update));
+
+    // Constraints only apply within a stage.
+    schedule.add_system(Stage::PreUpdate, logging("c", &log).after("b").before("a")).unwrap();
+    assert_eq!(schedule.system_names(Stage::Update), ["a", "b"]);
+}
+
+#[test]
+fn run_conditions_gate_systems_on_world_and_resources() {
+    let log = Log::default();
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(Stage::Update, logging("paused_only", &log).run_if(|_world, resources| resources.contains::<bool>()))
+        .unwrap();
+    schedule
+        .add_system(Stage::Update, logging("with_entities", &log).run_if(|world, _resources| !world.is_empty()))
+        .unwrap();
+
+    let mut world = World::new();
+    let mut resources = Resources::new();
+    schedule.run_stage(Stage::Update, &mut world, &resources);
+    assert!(log.borrow().is_empty());
+
+    resources.insert(true);
+    world.spawn((1u32,));
+    schedule.run_stage(Stage::Update, &mut world, &resources);
+    assert_eq!(*log.borrow(), ["paused_only", "with_entities"]);
+}
+
+#[test]
+fn systems_share_state_through_resources() {
+    let mut resources = Resources::new();
+    assert_eq!(resources.insert(1u32), None);
+    assert_eq!(resources.insert(2u32), Some(1));
+
+    let mut schedule = Schedule::new();
+    schedule
+        .add_system(Stage::Update, System::new("count", |_world, resources| *resources.get_mut::<u32>().unwrap() += 1))
+        .unwrap();
+    schedule.run_stage(Stage::Update, &mut World::new(), &resources);
+    schedule.run_stage(Stage::Update, &mut World::new(), &resources);
+
+    assert_eq!(*resources.get::<u32>().unwrap(), 4);
+    assert!(resources.get::<i64>().is_none());
+    assert_eq!(resources.remove::<u32>(), Some(4));
+    assert!(!resources.contains::<u32>());
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn borrowing_a_resource_mutably_twice_panics() {
+    let mut resources = Resources::new();
+    resources.insert(0u32);
+    let _first = resources.get_mut::<u32>().unwrap();
+    let _second = resources.get_mut::<u32>().unwrap();
+}
+
+#[test]
+fn engine_runs_stages_around_fixed_steps() {
+    let mut engine = pollster::block_on(Engine::new_headless(64, 64)).unwrap();
+    let log = Log::default();
+    for stage in Stage::ALL {
+        engine.add_system(stage, logging(&format!("{stage:?}"), &log)).unwrap();
+    }
+    let built_in = engine.schedule().system_names(Stage::FixedUpdate);
+    assert_eq!(built_in[0], "snapshot_transforms");
+    assert!(built_in.contains(&"detect_collider_overlaps") && built_in.contains(&"physics"));
+
+    let timestep = Duration::from_secs_f32(engine.physics_system().config().timestep);
+    engine.advance(timestep * 5 / 2);
+    engine.render();
+    assert_eq!(*log.borrow(), ["PreUpdate", "FixedUpdate", "FixedUpdate", "Update", "PostUpdate", "PreRender"]);
+
+    log.borrow_mut().clear();
+    engine.advance(Duration::ZERO);
+    assert_eq!(*log.borrow(), ["PreUpdate", "Update", "PostUpdate"]);
+}
+
+#[test]
+fn physics_steps_as_a_removable_system() {
+    let mut engine = pollster::block_on(Engine::new_headless(64, 64)).unwrap();
+    let ball = engine.ecs_world_mut().create_sprite_entity("player", Vec2::new(0.0, 100.0), 10.0, 10.0);
+    engine.ecs_world_mut().add_rigid_body(ball, Vec2::ZERO, 1.0).unwrap();
+    engine.ecs_world_mut().add_collider(ball, 10.0, 10.0, false).unwrap();
+
+    let timestep = Duration::from_secs_f32(engine.physics_system().config().timestep);
+    engine.advance(timestep * 3 / 2);
+    assert!(engine.physics_system().body_handle(ball).is_some());
+    let fallen = engine.ecs_world().world.get::<&Transform>(ball).unwrap().position;
+    assert!(fallen.y < 100.0);
+
+    // Without the system bodies stay put.
+    assert!(engine.schedule_mut().remove_system("physics"));
+    engine.advance(timestep * 3);
+    assert_eq!(engine.ecs_world().world.get::<&Transform>(ball).unwrap().position, fallen);
+    assert_eq!(engine.time().fixed_step_count(), 4);
+}
+
+#[test]
+fn systems_reach_engine_subsystems_through_resources() {
+    let mut engine = pollster::block_on(Engine::new_headless(64, 64)).unwrap();
+    let ball = engine.ecs_world_mut().create_sprite_entity("player", Vec2::new(0.0, 100.0), 10.0, 10.0);
+    engine.ecs_world_mut().add_rigid_body(ball, Vec2::ZERO, 1.0).unwrap();
+    engine.ecs_world_mut().add_collider(ball, 10.0, 10.0, false).unwrap();
+
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let log = seen.clone();
+    engine
+        .add_system(
+            Stage::Update,
+            System::new("observe", move |_world, resources| {
+                let time = resources.get::<Time>().unwrap();
+                let physics = resources.get::<PhysicsSystem>().unwrap();
+                let input = resources.get::<InputManager>().unwrap();
+                assert!(resources.contains::<AudioSystem>() && resources.contains::<ResourceManager>());
+                assert!(!input.is_key_pressed(PhysicalKey::Code(KeyCode::Space)));
+                log.borrow_mut().push((time.fixed_step_count(), physics.body_handle(ball).is_some()));
+            }),
+        )
+        .unwrap();
+
+    let timestep = Duration::from_secs_f32(engine.physics_system().config().timestep);
+    engine.advance(timestep * 3 / 2);
+    assert_eq!(*seen.borrow(), [(1, true)]);
+}
@@ -0,0 +1,141 @@
+use engine_2d::ecs::{EcsWorld, Transform};
+use engine_2d::physics::PhysicsSystem;
+use engine_2d::resources::Texture;
+use engine_2d::shapes::ColliderShape;
+use glam::Vec2;
+
+fn transform(position: Vec2, rotation: f32, scale: Vec2) -> Transform {
+    Transform { position, rotation, scale }
+}
+
+#[test]
+fn bounding_box_respects_rotation_and_scale() {
+    let shape = ColliderShape::cuboid(20.0, 10.0);
+
+    let (min, max) = shape.bounding_box(&transform(Vec2::new(5.0, 5.0), 0.0, Vec2::new(2.0, 1.0)));
+    assert_eq!((min, max), (Vec2::new(-15.0, 0.0), Vec2::new(25.0, 10.0)));
+
+    let (min, max) = shape.bounding_box(&transform(Vec2::ZERO, std::f32::consts::FRAC_PI_2, Vec2::ONE));
+    assert!((min - Vec2::new(-5.0, -10.0)).length() < 1e-4);
+    assert!((max - Vec2::new(5.0, 10.0)).length() < 1e-4);
+}
+
+#[test]
+fn round_bounding_boxes_are_exact_when_rotated() {
+    let ball = ColliderShape::Ball { radius: 10.0 };
+    let (min, max) = ball.bounding_box(&transform(Vec2::new(5.0, 0.0), 0.1, Vec2::new(-2.0, 2.0)));
+    assert!((min - Vec2::new(-15.0, -20.0)).length() < 1e-4, "{:?}", min);
+    assert!((max - Vec2::new(25.0, 20.0)).length() < 1e-4, "{:?}", max);
+
+    let capsule = ColliderShape::Capsule { half_height: 10.0, radius: 5.0 };
+    let (min, max) = capsule.bounding_box(&transform(Vec2::ZERO, std::f32::consts::FRAC_PI_2, Vec2::ONE));
+    assert!((min - Vec2::new(-15.0, -5.0)).length() < 1e-4, "{:?}", min);
+    assert!((max - Vec2::new(15.0, 5.0)).length() < 1e-4, "{:?}", max);
+}
+
+#[test]
+fn every_shape_converts_to_a_rapier_shape() {
+    let triangle = vec![Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0), Vec2::new(0.0, 10.0)];
+    let shapes = [
+        ColliderShape::cuboid(10.0, 10.0),
+        ColliderShape::Ball { radius: 5.0 },
+        ColliderShape::Capsule { half_height: 10.0, radius: 5.0 },
+        ColliderShape::ConvexPolygon { points: triangle.clone() },
+        ColliderShape::Polyline { points: triangle.clone() },
+        ColliderShape::TriMesh { vertices: triangle, indices: vec![[0, 1, 2]] },
+        ColliderShape::Compound(vec![
+            (Vec2::new(-10.0, 0.0), 0.0, ColliderShape::Ball { radius: 5.0 }),
+            (Vec2::new(10.0, 0.0), 0.5, ColliderShape::cuboid(4.0, 4.0)),
+        ]),
+    ];
+
+    for shape in &shapes {
+        assert!(shape.to_shared_shape(Vec2::ONE).is_ok(), "{:?}", shape);
+        assert!(shape.to_shared_shape(Vec2::new(2.0, 0.5)).is_ok(), "{:?}", shape);
+    }
+
+    assert!(ColliderShape::ConvexPolygon { points: vec![Vec2::ZERO, Vec2::X] }
+        .to_shared_shape(Vec2::ONE)
+        .is_err());
+}
+
+#[test]
+fn nested_compounds_are_flattened_and_meshes_rejected() {
+    let inner = ColliderShape::Compound(vec![(Vec2::new(10.0, 0.0), 0.0, ColliderShape::Ball { radius: 5.0 })]);
+    let outer = ColliderShape::Compound(vec![
+        (Vec2::new(0.0, 20.0), std::f32::consts::FRAC_PI_2, inner.clone()),
+        (Vec2::ZERO, 0.0, ColliderShape::cuboid(4.0, 4.0)),
+    ]);
+    let shape = outer.to_shared_shape(Vec2::ONE).unwrap();
+    let compound = shape.as_compound().unwrap();
+    assert_eq!(compound.shapes().len(), 2);
+    // The nested ball is rotated with its compound to sit above the origin.
+    let (placement, _) = &compound.shapes()[0];
+    assert!(placement.translation.x.abs() < 1e-4);
+    assert!((placement.translation.y - 30.0).abs() < 1e-4);
+
+    let polyline = ColliderShape::Polyline { points: vec![Vec2::ZERO, Vec2::X] };
+    let mesh = ColliderShape::TriMesh { vertices: vec![Vec2::ZERO, Vec2::X, Vec2::Y], indices: vec![[0, 1, 2]] };
+    for part in [polyline, mesh] {
+        let nested = ColliderShape::Compound(vec![(Vec2::ZERO, 0.0, ColliderShape::Compound(vec![(Vec2::ZERO, 0.0, part)]))]);
+        assert!(nested.to_shared_shape(Vec2::ONE).is_err());
+    }
+    assert!(ColliderShape::Compound(vec![(Vec2::ZERO, 0.0, ColliderShape::Compound(Vec::new()))])
+        .to_shared_shape(Vec2::ONE)
+        .is_err());
+}
+
+#[test]
+fn non_uniformly_scaled_ball_becomes_an_ellipse() {
+    let shape = ColliderShape::Ball { radius: 10.0 }
+        .to_shared_shape(Vec2::new(2.0, 1.0))
+        .unwrap();
+    let aabb = shape.compute_local_aabb();
+    assert!((aabb.maxs.x - 20.0).abs() < 1e-3);
+    assert!((aabb.maxs.y - 10.0).abs() < 1e-3);
+}
+
+#[test]
+fn alpha_hull_wraps_opaque_pixels() {
+    // 4x4 texture with a 2x2 opaque block in the top-left corner.
+    let mut data = vec![0u8; 4 * 4 * 4];
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        data[(y * 4 + x) * 4 + 3] = 255;
+    }
+    let texture = Texture { width: 4, height: 4, data };
+
+    let shape = ColliderShape::from_alpha_hull(&texture, Vec2::new(40.0, 40.0), 128).unwrap();
+    let (min, max) = shape.bounding_box(&transform(Vec2::ZERO, 0.0, Vec2::ONE));
+    assert_eq!((min, max), (Vec2::new(-20.0, 0.0), Vec2::new(0.0, 20.0)));
+
+    let empty = Texture { width: 2, height: 2, data: vec![0; 16] };
+    assert!(ColliderShape::from_alpha_hull(&empty, Vec2::ONE, 0).is_none());
+    let truncated = Texture { width: 4, height: 4, data: vec![255; 4 * 4 * 4 - 1] };
+    assert!(ColliderShape::from_alpha_hull(&truncated, Vec2::ONE, 0).is_none());
+}
+
+#[test]
+fn balls_roll_down_sloped_terrain() {
+    let mut world = EcsWorld::new();
+    let mut physics = PhysicsSystem::new();
+
+    // Long enough that the ball is still on it after four seconds of real gravity.
+    let slope = world.create_entity();
+    world.world.insert_one(slope, transform(Vec2::ZERO, 0.0, Vec2::ONE)).unwrap();
+    world.add_collider_shape(
+        slope,
+        ColliderShape::Polyline { points: vec![Vec2::new(-100.0, 100.0), Vec2::new(100.0, 0.0), Vec2::new(5000.0, -2450.0)] },
+        false,
+    ).unwrap();
+    let ball = world.create_sprite_entity("player", Vec2::new(-90.0, 110.0), 10.0, 10.0);
+    world.add_rigid_body(ball, Vec2::ZERO, 1.0).unwrap();
+    world.add_collider_shape(ball, ColliderShape::Ball { radius: 5.0 }, false).unwrap();
+
+    for _ in 0..240 {
+        physics.update(&mut world.world, 1.0 / 60.0);
+    }
+
+    let position = world.world.get::<&Transform>(ball).unwrap().position;
+    assert!(position.x > -80.0, "ball should roll downhill, x = {}", position.x);
+    assert!(position.y > 50.0 - position.x / 2.0, "ball should stay above the slope");
+}
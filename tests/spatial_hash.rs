@@ -0,0 +1,143 @@
+use engine_2d::ecs::{detect_collider_overlaps, EcsWorld, Transform};
+use engine_2d::spatial_hash::SpatialHash;
+use glam::Vec2;
+use hecs::Entity;
+
+/// Deterministic pseudo-random boxes of very different sizes over a 1000x1000 area,
+/// crowded enough to overlap often. Every 50th box is wide enough to be kept out of
+/// the grid.
+fn crowded_boxes(count: usize) -> Vec<(Entity, Vec2, Vec2)> {
+    let mut world = hecs::World::new();
+    let mut seed = 12345u32;
+    let mut next = || {
+        seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+        (seed >> 8) as f32 / (1u32 << 24) as f32
+    };
+    (0..count)
+        .map(|i| {
+            let min = Vec2::new(next() * 1000.0 - 500.0, next() * 1000.0 - 500.0);
+            let mut size = Vec2::new(next() * 60.0 + 1.0, next() * 60.0 + 1.0);
+            if i % 50 == 0 {
+                size.x *= 1000.0;
+            }
+            (world.spawn(()), min, min + size)
+        })
+        .collect()
+}
+
+fn brute_force_pairs(boxes: &[(Entity, Vec2, Vec2)]) -> Vec<(Entity, Entity)> {
+    let mut pairs = Vec::new();
+    for (i, (a, min_a, max_a)) in boxes.iter().enumerate() {
+        for (b, min_b, max_b) in &boxes[i + 1..] {
+            if max_a.x > min_b.x && min_a.x < max_b.x && max_a.y > min_b.y && min_a.y < max_b.y {
+                pairs.push(if a.to_bits() < b.to_bits() { (*a, *b) } else { (*b, *a) });
+            }
+        }
+    }
+    pairs.sort_unstable_by_key(|(a, b)| (a.to_bits(), b.to_bits()));
+    pairs
+}
+
+#[test]
+fn overlapping_pairs_match_brute_force() {
+    let boxes = crowded_boxes(500);
+    let mut hash = SpatialHash::new(32.0).unwrap();
+    for (entity, min, max) in &boxes {
+        hash.insert(*entity, *min, *max);
+    }
+
+    let pairs = hash.overlapping_pairs();
+    assert!(!pairs.is_empty());
+    assert_eq!(pairs, brute_force_pairs(&boxes));
+}
+
+#[test]
+fn aabb_and_radius_queries_find_nearby_boxes() {
+    let mut world = hecs::World::new();
+    let (near, far, touching) = (world.spawn(()), world.spawn(()), world.spawn(()));
+    let mut hash = SpatialHash::new(16.0).unwrap();
+    hash.insert(near, Vec2::new(0.0, 0.0), Vec2::new(10.0, 10.0));
+    hash.insert(far, Vec2::new(200.0, 200.0), Vec2::new(210.0, 210.0));
+    hash.insert(touching, Vec2::new(10.0, 0.0), Vec2::new(20.0, 10.0));
+
+    assert_eq!(hash.query_aabb(Vec2::new(-5.0, -5.0), Vec2::new(5.0, 5.0)), vec![near]);
+    assert_eq!(hash.query_aabb(Vec2::new(-500.0, -500.0), Vec2::new(500.0, 500.0)).len(), 3);
+    assert_eq!(hash.query_radius(Vec2::new(205.0, 190.0), 11.0), vec![far]);
+    assert!(hash.query_radius(Vec2::new(205.0, 190.0), 9.0).is_empty());
+    assert!(hash.overlapping_pairs().is_empty(), "touching edges don't overlap");
+}
+
+#[test]
+fn moving_and_removing_entities_updates_the_index() {
+    let mut world = hecs::World::new();
+    let (a, b) = (world.spawn(()), world.spawn(()));
+    let mut hash = SpatialHash::new(16.0).unwrap();
+    hash.insert(a, Vec2::ZERO, Vec2::splat(10.0));
+    hash.insert(b, Vec2::splat(100.0), Vec2::splat(110.0));
+    assert!(hash.overlapping_pairs().is_empty());
+
+    hash.insert(b, Vec2::splat(5.0), Vec2::splat(15.0));
+    assert_eq!(hash.overlapping_pairs().len(), 1);
+    assert!(hash.query_aabb(Vec2::splat(99.0), Vec2::splat(120.0)).is_empty(), "old cells are vacated");
+
+    assert!(hash.remove(a));
+    assert!(!hash.remove(a));
+    assert!(hash.overlapping_pairs().is_empty());
+    assert_eq!(hash.len(), 1);
+}
+
+#[test]
+fn huge_boxes_and_queries_stay_bounded() {
+    let mut world = hecs::World::new();
+    let (small, huge, far) = (world.spawn(()), world.spawn(()), world.spawn(()));
+    let mut hash = SpatialHash::new(1.0).unwrap();
+    hash.insert(small, Vec2::ZERO, Vec2::splat(2.0));
+    hash.insert(huge, Vec2::splat(-1e30), Vec2::splat(1e30));
+    hash.insert(far, Vec2::splat(1e20), Vec2::splat(1e20 + 1e15));
+
+    assert_eq!(hash.query_aabb(Vec2::splat(1.0), Vec2::splat(1.5)).len(), 2);
+    assert_eq!(hash.query_radius(Vec2::ZERO, f32::MAX).len(), 3);
+    assert_eq!(hash.query_aabb(Vec2::NEG_INFINITY, Vec2::INFINITY).len(), 3);
+    assert_eq!(hash.overlapping_pairs().len(), 2);
+
+    // Shrinking a box back to normal moves it into the grid.
+    hash.insert(huge, Vec2::splat(50.0), Vec2::splat(60.0));
+    assert_eq!(hash.query_aabb(Vec2::splat(1.0), Vec2::splat(1.5)), vec![small]);
+    assert!(hash.overlapping_pairs().is_empty());
+    hash.insert(small, Vec2::ZERO, Vec2::INFINITY);
+    assert!(!hash.contains(small));
+}
+
+#[test]
+fn cell_sizes_must_be_positive() {
+    for cell_size in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        assert!(SpatialHash::new(cell_size).is_err(), "{cell_size}");
+    }
+    let mut ecs = EcsWorld::new();
+    assert!(ecs.set_spatial_hash_cell_size(0.0).is_err());
+    assert!(ecs.set_spatial_hash_cell_size(32.0).is_ok());
+    assert_eq!(ecs.spatial_hash().cell_size(), 32.0);
+}
+
+#[test]
+fn detecting_overlaps_tracks_colliders_in_the_world() {
+    let mut ecs = EcsWorld::new();
+    let a = ecs.create_sprite_entity("player", Vec2::new(0.0, 0.0), 20.0, 20.0);
+    ecs.add_collider(a, 20.0, 20.0, false).unwrap();
+    let b = ecs.create_sprite_entity("player", Vec2::new(15.0, 0.0), 20.0, 20.0);
+    ecs.add_collider(b, 20.0, 20.0, false).unwrap();
+    let c = ecs.create_sprite_entity("player", Vec2::new(100.0, 0.0), 20.0, 20.0);
+    ecs.add_collider(c, 20.0, 20.0, false).unwrap();
+
+    detect_collider_overlaps(&mut ecs.world, &ecs.resources);
+    let expected = if a.to_bits() < b.to_bits() { (a, b) } else { (b, a) };
+    assert_eq!(*ecs.overlaps(), [expected]);
+
+    ecs.world.get::<&mut Transform>(c).unwrap().position = Vec2::new(20.0, 0.0);
+    ecs.world.despawn(a).unwrap();
+    detect_collider_overlaps(&mut ecs.world, &ecs.resources);
+    let expected = if b.to_bits() < c.to_bits() { (b, c) } else { (c, b) };
+    assert_eq!(*ecs.overlaps(), [expected]);
+    assert!(!ecs.spatial_hash().contains(a));
+    assert_eq!(ecs.spatial_hash().query_radius(Vec2::new(20.0, 0.0), 1.0).len(), 2);
+}
@@ -0,0 +1,73 @@
+use engine_2d::engine::Engine;
+use engine_2d::physics::PhysicsConfig;
+use engine_2d::time::MAX_TIME_SCALE;
+use std::time::Duration;
+
+const STEP: Duration = Duration::from_micros(15_625);
+
+/// Headless engine stepping every `STEP`, a timestep `f32` seconds represent exactly.
+fn engine() -> Engine<'static> {
+    let mut engine = pollster::block_on(Engine::new_headless(64, 64)).unwrap();
+    let config = PhysicsConfig { timestep: STEP.as_secs_f32(), ..PhysicsConfig::default() };
+    engine.physics_system_mut().set_config(config).unwrap();
+    engine
+}
+
+#[test]
+fn fixed_steps_are_counted_and_leftover_time_carries_over() {
+    let mut engine = engine();
+    engine.advance(STEP * 5 / 2);
+    assert_eq!(engine.time().fixed_step_count(), 2);
+    engine.advance(STEP / 4);
+    assert_eq!(engine.time().fixed_step_count(), 2);
+    engine.advance(STEP / 4);
+    assert_eq!(engine.time().fixed_step_count(), 3);
+    assert_eq!(engine.time().frame_count(), 3);
+    assert_eq!(engine.time().elapsed(), STEP * 3);
+}
+
+#[test]
+fn long_frames_are_clamped() {
+    let mut engine = engine();
+    engine.advance(Duration::from_secs(10));
+    let max_delta = Duration::from_millis(250);
+    assert_eq!(engine.time().delta(), max_delta);
+    assert_eq!(engine.time().fixed_step_count(), (max_delta.as_nanos() / STEP.as_nanos()) as u64);
+}
+
+#[test]
+fn alpha_is_the_fraction_of_a_step_left_over() {
+    let mut engine = engine();
+    engine.advance(STEP * 3 / 2);
+    assert!((engine.time().interpolation_alpha() - 0.5).abs() < 1e-4);
+    engine.advance(STEP * 2 / 5);
+    assert!((engine.time().interpolation_alpha() - 0.9).abs() < 1e-4);
+    engine.advance(STEP / 10);
+    assert!(engine.time().interpolation_alpha() < 1e-4);
+    assert_eq!(engine.time().fixed_step_count(), 2);
+}
+
+#[test]
+fn time_scale_slows_and_pauses_steps() {
+    let mut engine = engine();
+    engine.time_mut().set_time_scale(0.5).unwrap();
+    engine.advance(STEP * 4);
+    assert_eq!(engine.time().fixed_step_count(), 2);
+    engine.time_mut().set_time_scale(0.0).unwrap();
+    engine.advance(STEP * 4);
+    assert_eq!(engine.time().fixed_step_count(), 2);
+    assert_eq!(engine.time().elapsed(), STEP * 2);
+}
+
+#[test]
+fn time_scales_are_kept_finite() {
+    let mut engine = engine();
+    assert!(engine.time_mut().set_time_scale(f32::NAN).is_err());
+    assert!(engine.time_mut().set_time_scale(f32::INFINITY).is_err());
+    assert_eq!(engine.time().time_scale(), 1.0);
+
+    engine.time_mut().set_time_scale(1e30).unwrap();
+    assert_eq!(engine.time().time_scale(), MAX_TIME_SCALE);
+    engine.time_mut().set_time_scale(-2.0).unwrap();
+    assert_eq!(engine.time().time_scale(), 0.0);
+}